@@ -58,7 +58,15 @@ fn MyGroups(cx: Scope) -> impl IntoView {
 
             let add_group = move |_| {
                 let newname = txt_newgroup.get().unwrap().value().to_string();
-                let g = UsergroupData { title: newname };
+                let g = UsergroupData {
+                    title: newname,
+                    description: String::new(),
+                    parent: None,
+                    include_subgroups: true,
+                    tags: Vec::new(),
+                    public: false,
+                    deleted_at: None,
+                };
                 add_group_action.dispatch(g);
             };
 
@@ -45,7 +45,15 @@ async fn MyGroups<G: Html>(cx: Scope<'_>) -> View<G> {
                 let txt_new_group = create_signal(cx, String::new());
 
                 let add_group_click = |_| async {
-                    let g = UsergroupData { title: txt_new_group.get().to_string() };
+                    let g = UsergroupData {
+                        title: txt_new_group.get().to_string(),
+                        description: String::new(),
+                        parent: None,
+                        include_subgroups: true,
+                        tags: Vec::new(),
+                        public: false,
+                        deleted_at: None,
+                    };
                     let id = API::create_usergroup(&g).await;
                     match id {
                         Ok(id) => {
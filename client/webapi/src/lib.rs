@@ -2,7 +2,12 @@ use wasm_bindgen::{prelude::JsCast, JsValue};
 use wasm_bindgen_futures::JsFuture;
 use serde::{Serialize, Deserialize};
 
-use plebiscite_types::{Usergroup, UsergroupId, UsergroupData};
+use plebiscite_types::{Session, SessionId, UserId, Usergroup, UsergroupId, UsergroupData, Poll, PollId, PollData, PollInfo, PollState, Ballot, VoteReceipt, Delegation, CountingMethod, Role, Member, JoinRequest, Invite, InviteId, InviteData};
+use plebiscite_types::tally::PollResults;
+use plebiscite_types::proposal::{
+    Proposal, ProposalId, ProposalData, ProposalInfo, Comment, CommentId, CommentData,
+    Amendment, AmendmentId, AmendmentData, AmendmentState,
+};
 
 //--------------------------------------------------------------------
 
@@ -29,6 +34,23 @@ macro_rules! log {
 
 //--------------------------------------------------------------------
 
+/// The cookie the server hands out, echoed in the header of the changes
+const CSRF_TOKEN: &str = "csrf_token";
+const CSRF_HEADER: &str = "X-CSRF-Token";
+
+fn csrf_token(wnd: &web_sys::Window) -> Option<String> {
+    let doc: web_sys::HtmlDocument = wnd.document()?.dyn_into().ok()?;
+    let cookies = doc.cookie().ok()?;
+
+    cookies
+        .split(';')
+        .filter_map(|c| c.trim().split_once('='))
+        .find(|(name, _)| *name == CSRF_TOKEN)
+        .map(|(_, value)| value.to_owned())
+}
+
+//--------------------------------------------------------------------
+
 pub trait TypeConverter {
     type Error;
 
@@ -57,6 +79,8 @@ pub type FetchResult<T, TC> = Result<T, FetchError<TC>>;
 enum Method<T> {
     Get,
     Post(T),
+    Put(T),
+    Delete,
 }
 
 impl<T> Method<T> {
@@ -64,6 +88,8 @@ impl<T> Method<T> {
         match self {
             Self::Get => ("GET", None),
             Self::Post(x) => ("POST", Some(x)),
+            Self::Put(x) => ("PUT", Some(x)),
+            Self::Delete => ("DELETE", None),
         }
     }
 }
@@ -76,6 +102,25 @@ pub struct WebAPI<TC> {
 
 impl<TC: TypeConverter> WebAPI<TC> { 
 
+    /// Ends the current session
+    pub async fn logout() -> FetchResult<(), TC> {
+        Self::http_post("/api/logout", ()).await
+    }
+
+    /// The current user's active sessions
+    pub async fn get_sessions() -> FetchResult<Vec<Session>, TC> {
+        Self::http_get("/api/sessions").await
+    }
+
+    pub async fn revoke_session(session_id: SessionId) -> FetchResult<(), TC> {
+        Self::http_delete(&format!("/api/sessions/{}", session_id.value)).await
+    }
+
+    /// Only the site admins can unlock the users locked out after failed logins
+    pub async fn unlock_user(user_id: UserId) -> FetchResult<(), TC> {
+        Self::http_post(&format!("/api/users/{}/unlock", user_id.value), ()).await
+    }
+
     pub async fn get_assigned_usergroups() -> FetchResult<Vec<Usergroup>, TC> {
         Self::http_get("/api/user/groups").await
     }
@@ -84,6 +129,205 @@ impl<TC: TypeConverter> WebAPI<TC> {
         Self::http_post("/api/user/groups/create", data).await
     }
 
+    /// Only the group's admins can edit it, the parent is set by `set_usergroup_parent`
+    pub async fn update_usergroup(group_id: UsergroupId, data: &UsergroupData) -> FetchResult<(), TC> {
+        Self::http_put(&format!("/api/groups/{}", group_id.value), data).await
+    }
+
+    /// Only the group's owners can delete it. Unless `permanent`, the group is hidden
+    /// until restored, otherwise it is gone with its polls, votes and proposals.
+    pub async fn delete_usergroup(group_id: UsergroupId, permanent: bool) -> FetchResult<(), TC> {
+        Self::http_delete(&format!("/api/groups/{}?permanent={}", group_id.value, permanent)).await
+    }
+
+    pub async fn restore_usergroup(group_id: UsergroupId) -> FetchResult<(), TC> {
+        Self::http_post(&format!("/api/groups/{}/restore", group_id.value), ()).await
+    }
+
+    /// The whole tree under the group, each subgroup after its parent
+    pub async fn get_usergroup_subgroups(group_id: UsergroupId) -> FetchResult<Vec<Usergroup>, TC> {
+        Self::http_get(&format!("/api/groups/{}/subgroups", group_id.value)).await
+    }
+
+    /// Only the group's owners can move it, under a group they are an admin of
+    pub async fn set_usergroup_parent(group_id: UsergroupId, parent: Option<UsergroupId>) -> FetchResult<(), TC> {
+        Self::http_post(&format!("/api/groups/{}/parent", group_id.value), parent).await
+    }
+
+    pub async fn set_usergroup_include_subgroups(group_id: UsergroupId, include: bool) -> FetchResult<(), TC> {
+        Self::http_post(&format!("/api/groups/{}/include_subgroups", group_id.value), include).await
+    }
+
+    pub async fn set_usergroup_tags(group_id: UsergroupId, tags: &[String]) -> FetchResult<(), TC> {
+        Self::http_post(&format!("/api/groups/{}/tags", group_id.value), tags).await
+    }
+
+    pub async fn set_usergroup_public(group_id: UsergroupId, public: bool) -> FetchResult<(), TC> {
+        Self::http_post(&format!("/api/groups/{}/public", group_id.value), public).await
+    }
+
+    /// The public groups with the words of `query` in the title and the `tag`, if given
+    pub async fn search_usergroups(query: Option<&str>, tag: Option<&str>) -> FetchResult<Vec<Usergroup>, TC> {
+        let mut params = Vec::new();
+        if let Some(query) = query {
+            params.push(format!("q={}", js_sys::encode_uri_component(query)));
+        }
+        if let Some(tag) = tag {
+            params.push(format!("tag={}", js_sys::encode_uri_component(tag)));
+        }
+        Self::http_get(&format!("/api/groups/search?{}", params.join("&"))).await
+    }
+
+    /// Asks the admins of a public group to join it
+    pub async fn request_join_usergroup(group_id: UsergroupId) -> FetchResult<(), TC> {
+        Self::http_post(&format!("/api/groups/{}/join_requests", group_id.value), ()).await
+    }
+
+    pub async fn get_join_requests(group_id: UsergroupId) -> FetchResult<Vec<JoinRequest>, TC> {
+        Self::http_get(&format!("/api/groups/{}/join_requests", group_id.value)).await
+    }
+
+    pub async fn answer_join_request(group_id: UsergroupId, user_id: UserId, accept: bool) -> FetchResult<(), TC> {
+        Self::http_post(&format!("/api/groups/{}/join_requests/{}", group_id.value, user_id.value), accept).await
+    }
+
+    pub async fn get_usergroup_polls(group_id: UsergroupId) -> FetchResult<Vec<Poll>, TC> {
+        Self::http_get(&format!("/api/groups/{}/polls", group_id.value)).await
+    }
+
+    pub async fn create_poll(group_id: UsergroupId, data: &PollData) -> FetchResult<PollId, TC> {
+        Self::http_post(&format!("/api/groups/{}/polls", group_id.value), data).await
+    }
+
+    pub async fn get_poll(group_id: UsergroupId, poll_id: PollId) -> FetchResult<PollInfo, TC> {
+        Self::http_get(&format!("/api/groups/{}/polls/{}", group_id.value, poll_id.value)).await
+    }
+
+    /// Only the author can edit a poll, and only while it is a draft
+    pub async fn update_poll(poll_id: PollId, data: &PollData) -> FetchResult<(), TC> {
+        Self::http_put(&format!("/api/polls/{}", poll_id.value), data).await
+    }
+
+    pub async fn set_poll_state(poll_id: PollId, state: PollState) -> FetchResult<(), TC> {
+        Self::http_post(&format!("/api/polls/{}/state", poll_id.value), state).await
+    }
+
+    pub async fn cast_vote(poll_id: PollId, ballot: &Ballot) -> FetchResult<VoteReceipt, TC> {
+        Self::http_post(&format!("/api/polls/{}/vote", poll_id.value), ballot).await
+    }
+
+    /// Commitments of all the ballots of a closed poll
+    pub async fn get_poll_receipts(poll_id: PollId) -> FetchResult<Vec<String>, TC> {
+        Self::http_get(&format!("/api/polls/{}/receipts", poll_id.value)).await
+    }
+
+    /// Results counted so far, ranked ballots by the poll's own method unless another one is given.
+    /// Fails for the voters of a poll which hides its results until it closes.
+    pub async fn get_poll_results(poll_id: PollId, method: Option<CountingMethod>) -> FetchResult<PollResults, TC> {
+        let url = match method {
+            Some(method) => format!("/api/polls/{}/results?method={}", poll_id.value, method.as_str()),
+            None => format!("/api/polls/{}/results", poll_id.value),
+        };
+        Self::http_get(&url).await
+    }
+
+    pub async fn get_usergroup_members(group_id: UsergroupId) -> FetchResult<Vec<Member>, TC> {
+        Self::http_get(&format!("/api/groups/{}/members", group_id.value)).await
+    }
+
+    /// Only the group's admins can remove the members, and only the owners the admins and owners
+    pub async fn remove_usergroup_member(group_id: UsergroupId, user_id: UserId) -> FetchResult<(), TC> {
+        Self::http_delete(&format!("/api/groups/{}/members/{}", group_id.value, user_id.value)).await
+    }
+
+    /// The group's last owner cannot leave it
+    pub async fn leave_usergroup(group_id: UsergroupId) -> FetchResult<(), TC> {
+        Self::http_post(&format!("/api/groups/{}/leave", group_id.value), ()).await
+    }
+
+    /// Only the group's owners can grant or revoke the owner and admin roles
+    pub async fn set_member_role(group_id: UsergroupId, user_id: UserId, role: Role) -> FetchResult<(), TC> {
+        Self::http_post(&format!("/api/groups/{}/members/{}/role", group_id.value, user_id.value), role).await
+    }
+
+    /// Only the group's admins can set the weights, from 1 to 1000000. While a secret poll
+    /// is pending, the weights of its voters stay 1.
+    pub async fn set_member_weight(group_id: UsergroupId, user_id: UserId, weight: i32) -> FetchResult<(), TC> {
+        Self::http_post(&format!("/api/groups/{}/members/{}/weight", group_id.value, user_id.value), weight).await
+    }
+
+    /// Only the group's admins can create invites, the result is the invite code
+    pub async fn create_invite(group_id: UsergroupId, invite: &InviteData) -> FetchResult<String, TC> {
+        Self::http_post(&format!("/api/groups/{}/invites", group_id.value), invite).await
+    }
+
+    /// The group's invites which can still be used
+    pub async fn get_usergroup_invites(group_id: UsergroupId) -> FetchResult<Vec<Invite>, TC> {
+        Self::http_get(&format!("/api/groups/{}/invites", group_id.value)).await
+    }
+
+    pub async fn revoke_invite(invite_id: InviteId) -> FetchResult<(), TC> {
+        Self::http_post(&format!("/api/invites/{}/revoke", invite_id.value), ()).await
+    }
+
+    /// Joins the current user to the invite's group
+    pub async fn redeem_invite(code: &str) -> FetchResult<UsergroupId, TC> {
+        Self::http_post(&format!("/api/invites/redeem/{}", code), ()).await
+    }
+
+    /// The current user's delegations in the group
+    pub async fn get_delegations(group_id: UsergroupId) -> FetchResult<Vec<Delegation>, TC> {
+        Self::http_get(&format!("/api/groups/{}/delegations", group_id.value)).await
+    }
+
+    pub async fn set_delegation(group_id: UsergroupId, delegation: &Delegation) -> FetchResult<(), TC> {
+        Self::http_post(&format!("/api/groups/{}/delegations", group_id.value), delegation).await
+    }
+
+    /// Revokes the delegation for the poll, or the group-wide one if `poll_id` is None
+    pub async fn revoke_delegation(group_id: UsergroupId, poll_id: Option<PollId>) -> FetchResult<(), TC> {
+        Self::http_post(&format!("/api/groups/{}/delegations/revoke", group_id.value), poll_id).await
+    }
+
+    //---------------------------------------------------------------
+
+    pub async fn get_usergroup_proposals(group_id: UsergroupId) -> FetchResult<Vec<Proposal>, TC> {
+        Self::http_get(&format!("/api/groups/{}/proposals", group_id.value)).await
+    }
+
+    pub async fn create_proposal(group_id: UsergroupId, data: &ProposalData) -> FetchResult<ProposalId, TC> {
+        Self::http_post(&format!("/api/groups/{}/proposals", group_id.value), data).await
+    }
+
+    pub async fn get_proposal(proposal_id: ProposalId) -> FetchResult<ProposalInfo, TC> {
+        Self::http_get(&format!("/api/proposals/{}", proposal_id.value)).await
+    }
+
+    pub async fn get_proposal_comments(proposal_id: ProposalId) -> FetchResult<Vec<Comment>, TC> {
+        Self::http_get(&format!("/api/proposals/{}/comments", proposal_id.value)).await
+    }
+
+    pub async fn add_proposal_comment(proposal_id: ProposalId, data: &CommentData) -> FetchResult<CommentId, TC> {
+        Self::http_post(&format!("/api/proposals/{}/comments", proposal_id.value), data).await
+    }
+
+    pub async fn get_amendments(proposal_id: ProposalId) -> FetchResult<Vec<Amendment>, TC> {
+        Self::http_get(&format!("/api/proposals/{}/amendments", proposal_id.value)).await
+    }
+
+    pub async fn add_amendment(proposal_id: ProposalId, data: &AmendmentData) -> FetchResult<AmendmentId, TC> {
+        Self::http_post(&format!("/api/proposals/{}/amendments", proposal_id.value), data).await
+    }
+
+    pub async fn set_amendment_state(amendment_id: AmendmentId, state: AmendmentState) -> FetchResult<(), TC> {
+        Self::http_post(&format!("/api/amendments/{}/state", amendment_id.value), state).await
+    }
+
+    /// Creates a draft poll out of the proposal and its accepted amendments
+    pub async fn promote_proposal(proposal_id: ProposalId) -> FetchResult<PollId, TC> {
+        Self::http_post(&format!("/api/proposals/{}/promote", proposal_id.value), ()).await
+    }
+
     //---------------------------------------------------------------
 
     async fn http_get<T>(url: &str) -> FetchResult<T, TC>
@@ -99,6 +343,19 @@ impl<TC: TypeConverter> WebAPI<TC> {
         Self::fetch(url, Method::Post(body)).await
     }
 
+    async fn http_put<T, U>(url: &str, body: U) -> FetchResult<T, TC>
+    where U: Serialize,
+          T: for<'a> Deserialize<'a>
+    {
+        Self::fetch(url, Method::Put(body)).await
+    }
+
+    async fn http_delete<T>(url: &str) -> FetchResult<T, TC>
+    where T: for<'a> Deserialize<'a>
+    {
+        Self::fetch::<T, ()>(url, Method::Delete).await
+    }
+
     //---------------------------------------------------------------
 
     async fn fetch<T, U>(url: &str, method: Method<U>) -> FetchResult<T, TC>
@@ -118,15 +375,23 @@ impl<TC: TypeConverter> WebAPI<TC> {
         let mut opts = web_sys::RequestInit::new();
         opts.method(verb);
 
+        let headers = web_sys::Headers::new().expect("Cannot create fetch Headers");
+
         if let Some(body) = body {
             let body = TC::serialize(body).map_err(err!(Serialize))?;
             opts.body(Some(&body));
 
-            let headers = web_sys::Headers::new().expect("Cannot create fetch Headers");
             headers.set("Content-Type", TC::content_type()).expect("Cannot set fetch Content-Type header");
-            opts.headers(&headers);
         }
 
+        if verb != "GET" {
+            if let Some(token) = csrf_token(&wnd) {
+                headers.set(CSRF_HEADER, &token).expect("Cannot set fetch CSRF header");
+            }
+        }
+
+        opts.headers(&headers);
+
         opts.credentials(web_sys::RequestCredentials::SameOrigin);
         opts.mode(web_sys::RequestMode::SameOrigin);
 
@@ -0,0 +1,52 @@
+use std::future::Future;
+use std::time::Duration;
+
+use crate::db_driver::{DbDriver, DbResult};
+
+//-------------------------------------------------------------
+
+const POLL_STATES_PERIOD: Duration = Duration::from_secs(30);
+const SESSION_PURGE_PERIOD: Duration = Duration::from_secs(600);
+const LOGIN_FAILURES_PURGE_PERIOD: Duration = Duration::from_secs(3600);
+
+/// Runs the job every period, logging the rows it changed and its failures
+fn spawn_periodic<F, Fut>(drv: DbDriver, period: Duration, what: &'static str, job: F)
+where
+    F: Fn(DbDriver) -> Fut + 'static,
+    Fut: Future<Output = DbResult<i32>>,
+{
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(period);
+
+        loop {
+            interval.tick().await;
+
+            match job(drv.clone()).await {
+                Ok(0) => {},
+                Ok(changed) => println!("Periodic {}: {} rows", what, changed),
+                Err(e) => println!("Periodic {} failed: {}", what, e),
+            }
+        }
+    });
+}
+
+/// Periodically opens and closes the polls at their scheduled times
+pub fn spawn_poll_state_updates(drv: DbDriver) {
+    spawn_periodic(drv, POLL_STATES_PERIOD, "poll state update", |drv| async move {
+        drv.advance_poll_states().await
+    });
+}
+
+/// Periodically deletes the expired sessions
+pub fn spawn_session_purge(drv: DbDriver) {
+    spawn_periodic(drv, SESSION_PURGE_PERIOD, "expired session purge", |drv| async move {
+        drv.purge_expired_sessions().await
+    });
+}
+
+/// Periodically deletes the failed logins which do not count any more
+pub fn spawn_login_failures_purge(drv: DbDriver) {
+    spawn_periodic(drv, LOGIN_FAILURES_PURGE_PERIOD, "login failure purge", |drv| async move {
+        drv.purge_login_failures().await
+    });
+}
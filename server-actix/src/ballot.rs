@@ -0,0 +1,113 @@
+use plebiscite_types::{Ballot, OptionIdx};
+
+use crate::db_driver::PollTallyInfo;
+
+//-------------------------------------------------------------
+
+/// Checks the ballot against the kind and the options of the poll
+pub fn validate(ballot: &Ballot, poll: &PollTallyInfo) -> Result<(), &'static str> {
+    if ballot.kind() != poll.kind {
+        return Err("Ballot does not match the poll kind");
+    }
+
+    let option_count = poll.option_count as usize;
+
+    match ballot {
+        Ballot::Single(option) => check_options(&[*option], option_count),
+        Ballot::Ranked(options) => {
+            if options.is_empty() {
+                return Err("Ranking is empty");
+            }
+            check_options(options, option_count)
+        },
+        Ballot::Approval(options) => check_options(options, option_count),
+        Ballot::Score(scores) => {
+            if scores.len() != option_count {
+                return Err("Every option must be scored");
+            }
+
+            let max_score = poll.max_score.unwrap_or(0);
+            if scores.iter().any(|&score| !(0..=max_score).contains(&score)) {
+                return Err("Score is out of range");
+            }
+            Ok(())
+        },
+    }
+}
+
+fn check_options(options: &[OptionIdx], option_count: usize) -> Result<(), &'static str> {
+    if options.iter().any(|&opt| usize::try_from(opt).map_or(true, |idx| idx >= option_count)) {
+        return Err("No such option in the poll");
+    }
+
+    if options.iter().enumerate().any(|(pos, opt)| options[..pos].contains(opt)) {
+        return Err("An option is chosen more than once");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use plebiscite_types::{CountingMethod, PollKind};
+
+    /// A poll of 3 options, scored from 0 to 5 if of the `Score` kind
+    fn poll(kind: PollKind) -> PollTallyInfo {
+        PollTallyInfo {
+            kind,
+            counting_method: CountingMethod::InstantRunoff,
+            option_count: 3,
+            max_score: Some(5),
+            seats: None,
+            quorum: Default::default(),
+            threshold: Default::default(),
+            eligible_voters: 10,
+        }
+    }
+
+    #[test]
+    fn valid_ballots() {
+        assert_eq!(validate(&Ballot::Single(2), &poll(PollKind::SingleChoice)), Ok(()));
+        assert_eq!(validate(&Ballot::Ranked(vec![2, 0]), &poll(PollKind::Ranked)), Ok(()));
+        assert_eq!(validate(&Ballot::Approval(vec![]), &poll(PollKind::Approval)), Ok(()));
+        assert_eq!(validate(&Ballot::Score(vec![0, 5, 3]), &poll(PollKind::Score)), Ok(()));
+    }
+
+    #[test]
+    fn kind_mismatch() {
+        assert_eq!(
+            validate(&Ballot::Single(0), &poll(PollKind::Ranked)),
+            Err("Ballot does not match the poll kind")
+        );
+    }
+
+    #[test]
+    fn no_such_option() {
+        let poll = poll(PollKind::Approval);
+        assert_eq!(validate(&Ballot::Approval(vec![0, 3]), &poll), Err("No such option in the poll"));
+        assert_eq!(validate(&Ballot::Approval(vec![-1]), &poll), Err("No such option in the poll"));
+    }
+
+    #[test]
+    fn duplicate_option() {
+        assert_eq!(
+            validate(&Ballot::Ranked(vec![1, 0, 1]), &poll(PollKind::Ranked)),
+            Err("An option is chosen more than once")
+        );
+    }
+
+    #[test]
+    fn empty_ranking() {
+        assert_eq!(validate(&Ballot::Ranked(vec![]), &poll(PollKind::Ranked)), Err("Ranking is empty"));
+    }
+
+    #[test]
+    fn scores_of_every_option_in_range() {
+        let poll = poll(PollKind::Score);
+        assert_eq!(validate(&Ballot::Score(vec![1, 2]), &poll), Err("Every option must be scored"));
+        assert_eq!(validate(&Ballot::Score(vec![1, 2, 3, 4]), &poll), Err("Every option must be scored"));
+        assert_eq!(validate(&Ballot::Score(vec![0, 6, 0]), &poll), Err("Score is out of range"));
+        assert_eq!(validate(&Ballot::Score(vec![0, -1, 0]), &poll), Err("Score is out of range"));
+    }
+}
@@ -0,0 +1,230 @@
+//! Defence against cross-site request forgery.
+//!
+//! Besides the cookies being `SameSite`, a state changing request must come
+//! from one of our pages when the browser tells where it comes from (`Origin`,
+//! or else `Referer`). Within the session scope it must also echo the
+//! `csrf_token` cookie in the `X-CSRF-Token` header, which a page of another
+//! site cannot read. The cookie is handed out with any response to a request
+//! lacking it, so the client has it before its first change.
+
+use std::future::{ Ready, ready };
+use std::rc::Rc;
+
+use actix_web::HttpRequest;
+use actix_web::cookie::{ Cookie, SameSite };
+use actix_web::dev::{ self, ServiceRequest, ServiceResponse, Service };
+use actix_web::http::{ header, Method };
+
+use futures_util::future::LocalBoxFuture;
+use rand_core::{ OsRng, RngCore };
+
+use crate::password::constant_time_eq;
+
+
+//-------------------------------------------------------------
+
+pub const CSRF_TOKEN: &str = "csrf_token";
+pub const CSRF_HEADER: &str = "X-CSRF-Token";
+
+
+//-------------------------------------------------------------
+
+fn token_cookie() -> Cookie<'static> {
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    let token: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+
+    // readable by the client, which sends it back in the header
+    Cookie::build(CSRF_TOKEN, token)
+        .path("/")
+        .same_site(SameSite::Lax)
+        .finish()
+}
+
+/// The host and port of an `Origin` or `Referer` value
+fn source_host(value: &str) -> Option<&str> {
+    let (_, rest) = value.split_once("://")?;
+    rest.split(['/', '?', '#']).next()
+}
+
+/// The requests telling neither `Origin` nor `Referer` are not made by a browser
+fn is_same_origin(req: &HttpRequest) -> bool {
+    let headers = req.headers();
+    match headers.get(header::ORIGIN).or_else(|| headers.get(header::REFERER)) {
+        None => true,
+        Some(value) => value
+            .to_str()
+            .ok()
+            .and_then(source_host)
+            .is_some_and(|host| host == req.connection_info().host()),
+    }
+}
+
+fn is_token_echoed(req: &HttpRequest) -> bool {
+    let sent = req.headers().get(CSRF_HEADER).and_then(|value| value.to_str().ok());
+
+    match (req.cookie(CSRF_TOKEN), sent) {
+        (Some(cookie), Some(sent)) => !sent.is_empty() && constant_time_eq(cookie.value().as_bytes(), sent.as_bytes()),
+        _ => false,
+    }
+}
+
+//----------------------------------------------------------------
+
+pub struct CsrfMiddleware<S> {
+    service: Rc<S>,
+    check_token: bool,
+}
+
+impl<S, B> Service<ServiceRequest> for CsrfMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = LocalBoxFuture<'static, Result<S::Response, Self::Error>>;
+
+    actix_web::dev::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+
+        let srv = self.service.clone();
+        let check_token = self.check_token;
+
+        Box::pin(async move {
+
+            let safe = matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+
+            if !safe && !is_same_origin(req.request()) {
+                println!("CsrfMiddleware: cross-site {} {}", req.method(), req.path());
+                return Err(actix_web::error::ErrorForbidden("Cross-site request"));
+            }
+
+            if !safe && check_token && !is_token_echoed(req.request()) {
+                println!("CsrfMiddleware: no token for {} {}", req.method(), req.path());
+                return Err(actix_web::error::ErrorForbidden("CSRF token mismatch"));
+            }
+
+            // the scopes checking the token are within the app, which hands it out
+            let hand_out = !check_token && req.cookie(CSRF_TOKEN).is_none();
+
+            let mut res = srv.call(req).await?;
+            if hand_out {
+                res.response_mut().add_cookie(&token_cookie())?;
+            }
+
+            Ok(res)
+        })
+
+    }
+
+}
+
+pub struct CsrfMiddlewareFactory {
+    check_token: bool,
+}
+
+impl CsrfMiddlewareFactory {
+    /// Checks the origin of the changes and hands out the token, for the whole app
+    pub fn new() -> Self {
+        CsrfMiddlewareFactory { check_token: false }
+    }
+
+    /// Also requires the token, for the scopes of the logged in users
+    pub fn with_token() -> Self {
+        CsrfMiddlewareFactory { check_token: true }
+    }
+}
+
+impl<S, B> dev::Transform<S, ServiceRequest> for CsrfMiddlewareFactory
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = S::Response;
+    type Error = actix_web::Error;
+    type InitError = ();
+    type Transform = CsrfMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(CsrfMiddleware {
+            service: Rc::new(service),
+            check_token: self.check_token,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    fn request(headers: &[(header::HeaderName, &str)]) -> HttpRequest {
+        let mut req = TestRequest::default().insert_header((header::HOST, "pleb.example:8080"));
+        for (name, value) in headers {
+            req = req.insert_header((name.clone(), *value));
+        }
+        req.to_http_request()
+    }
+
+    #[test]
+    fn source_host_keeps_the_port_only() {
+        assert_eq!(source_host("https://pleb.example:8080"), Some("pleb.example:8080"));
+        assert_eq!(source_host("https://pleb.example:8080/groups/1?tab=polls#top"), Some("pleb.example:8080"));
+        assert_eq!(source_host("https://pleb.example?q"), Some("pleb.example"));
+        assert_eq!(source_host("null"), None);
+    }
+
+    #[test]
+    fn origin() {
+        assert!(is_same_origin(&request(&[(header::ORIGIN, "https://pleb.example:8080")])));
+        assert!(!is_same_origin(&request(&[(header::ORIGIN, "https://pleb.example")])));
+        assert!(!is_same_origin(&request(&[(header::ORIGIN, "https://pleb.example:8080.evil.example")])));
+        assert!(!is_same_origin(&request(&[(header::ORIGIN, "null")])));
+    }
+
+    #[test]
+    fn origin_before_referer() {
+        let req = request(&[
+            (header::ORIGIN, "https://evil.example"),
+            (header::REFERER, "https://pleb.example:8080/groups"),
+        ]);
+        assert!(!is_same_origin(&req));
+    }
+
+    #[test]
+    fn referer_without_origin() {
+        assert!(is_same_origin(&request(&[(header::REFERER, "https://pleb.example:8080/groups/1")])));
+        assert!(!is_same_origin(&request(&[(header::REFERER, "https://evil.example/pleb.example:8080")])));
+    }
+
+    #[test]
+    fn neither_header() {
+        assert!(is_same_origin(&request(&[])));
+    }
+
+    #[test]
+    fn token() {
+        let echoed = |cookie: Option<&str>, sent: Option<&str>| {
+            let mut req = TestRequest::default();
+            if let Some(cookie) = cookie {
+                req = req.cookie(Cookie::new(CSRF_TOKEN, cookie));
+            }
+            if let Some(sent) = sent {
+                req = req.insert_header((CSRF_HEADER, sent));
+            }
+            is_token_echoed(&req.to_http_request())
+        };
+
+        assert!(echoed(Some("0123abcd"), Some("0123abcd")));
+        assert!(!echoed(Some("0123abcd"), Some("0123abce")));
+        assert!(!echoed(Some("0123abcd"), Some("0123abcd0")));
+        assert!(!echoed(Some(""), Some("")));
+        assert!(!echoed(Some("0123abcd"), None));
+        assert!(!echoed(None, Some("0123abcd")));
+    }
+}
@@ -48,6 +48,20 @@ macro_rules! pg_fn_vector {
     };
 }
 
+#[macro_export]
+macro_rules! pg_fn_exec {
+    ($db:ident, $fn:literal, [$($args:expr),*])  => {
+        {
+            const QUERY: &'static str = make_pg_fn_query!($fn, [$($args);*], ());
+            //println!("{}", QUERY);
+
+            $db.query_opt(QUERY, &[$($args),*])
+                .await
+                .map(|_| ())
+        }
+    };
+}
+
 //--------------------------------------------------------------------------
 
 macro_rules! make_pg_fn_query {
@@ -195,9 +209,9 @@ macro_rules! extract_value {
     };
 
     ( { $cont_fn:ident, $cont_inner:tt, $cont_acc:tt, $cont_context:tt },
-        $idx:expr, $row:ident, [$substruct:ident { $($subflds:tt)+ } $($tail:tt)*]) => {
+        $idx:expr, $row:ident, [$substruct:ident { $($subflds:tt)+ } $(, $($tail:tt)*)?]) => {
         init_struct!(
-            { $cont_fn, $cont_inner, $cont_acc, [$($tail)*], $cont_context },
+            { $cont_fn, $cont_inner, $cont_acc, [$($($tail)*)?], $cont_context },
             $idx, $row, [], [$($subflds)+], $substruct
         )
     };
@@ -207,9 +221,9 @@ macro_rules! extract_value {
     };
 
     ( { $cont_fn:ident, $cont_inner:tt, $cont_acc:tt, $cont_context:tt },
-        $idx:expr, $row:ident, [($($subtuple:tt)+) $($tail:tt)*]) => {
+        $idx:expr, $row:ident, [($($subtuple:tt)+) $(, $($tail:tt)*)?]) => {
         init_tuple!(
-            { $cont_fn, $cont_inner, $cont_acc, [$($tail)*], $cont_context },
+            { $cont_fn, $cont_inner, $cont_acc, [$($($tail)*)?], $cont_context },
             $idx, $row, [], [$($subtuple)+]
         )
     };
@@ -221,7 +235,7 @@ macro_rules! init_tuple {
 
     //({}, $idx_:tt, $_row:tt, [ $($val:tt)+ ], []) => {
     //    ( $($val),+ )
-    ({}, $idx_:tt, $_row:tt, [ $( ( $($val:tt)+ ) )+ ], []) => {
+    ({}, $_idx:expr, $_row:tt, [ $( ( $($val:tt)+ ) )+ ], []) => {
         ( $( $($val)+ ),+ )
     };
 
@@ -1,9 +1,21 @@
 use std::fmt;
+use std::time::Duration;
 use deadpool_postgres::{Config, Object, Pool};
 use tokio_postgres::{types::ToSql, Row, Statement};
 use uuid::Uuid;
 
-use plebiscite_types::{UserData, UserId, Usergroup, UsergroupId, UsergroupData};
+use plebiscite_types::{
+    UserData, UserId, Session, SessionId, SessionInfo, Usergroup, UsergroupId, UsergroupData,
+    Poll, PollId, PollData, PollInfo, PollKind, PollState, CountingMethod, Ballot, VoteReceipt, Quorum, Threshold,
+    Delegation, Role, Member, MemberInfo, JoinRequest, JoinRequestInfo, Invite, InviteId, InviteData, InviteInfo,
+};
+use plebiscite_types::proposal::{
+    Proposal, ProposalId, ProposalData, ProposalInfo, Comment, CommentId, CommentData, CommentInfo,
+    Amendment, AmendmentId, AmendmentData, AmendmentInfo, AmendmentState,
+};
+
+use crate::password::{Passwords, Verified};
+use crate::session::SessionTimeouts;
 
 #[macro_use]
 mod macros;
@@ -16,6 +28,31 @@ pub enum DbError {
     Pool(PoolError),
     Postgres(PgError),
     NoResult,
+    Forbidden(String),
+    NotFound(String),
+    Conflict(String),
+    Invalid(String),
+    /// Failed logins were tried too often, the seconds to wait
+    TooManyAttempts(i32),
+}
+
+impl From<PgError> for DbError {
+    /// Errors raised by our pgsql functions carry a custom SQLSTATE
+    /// in the `PL` class, which is mapped onto a dedicated variant.
+    fn from(e: PgError) -> Self {
+        let Some(db_err) = e.as_db_error() else {
+            return DbError::Postgres(e);
+        };
+
+        let msg = db_err.message().to_owned();
+        match db_err.code().code() {
+            "PL403" => DbError::Forbidden(msg),
+            "PL404" => DbError::NotFound(msg),
+            "PL409" => DbError::Conflict(msg),
+            "PL422" => DbError::Invalid(msg),
+            _ => DbError::Postgres(e),
+        }
+    }
 }
 
 impl fmt::Display for DbError {
@@ -24,6 +61,11 @@ impl fmt::Display for DbError {
             DbError::Pool(e) => write!(f, "Pool error: {}", e),
             DbError::Postgres(e) => write!(f, "Db error: {}", e),
             DbError::NoResult => write!(f, "No result from database"),
+            DbError::Forbidden(msg) => write!(f, "Forbidden: {}", msg),
+            DbError::NotFound(msg) => write!(f, "Not found: {}", msg),
+            DbError::Conflict(msg) => write!(f, "Conflict: {}", msg),
+            DbError::Invalid(msg) => write!(f, "Invalid: {}", msg),
+            DbError::TooManyAttempts(secs) => write!(f, "Too many attempts, retry after {}s", secs),
         }
     }
 }
@@ -33,14 +75,46 @@ pub type DbResult<T> = Result<T, DbError>;
 #[derive(Clone)]
 pub struct DbDriver {
     db_pool: Pool,
+    passwords: Passwords,
+    session_timeouts: SessionTimeouts,
+}
+
+struct Credentials {
+    user_id: UserId,
+    /// Argon2 hash, or the legacy plain text password
+    password: String,
 }
 
 #[derive(Clone, Debug)]
 pub struct User {
+    /// The session the user is logged in with
+    pub session_id: Uuid,
     pub user_id: UserId,
     pub data: UserData,
 }
 
+#[derive(Clone, Debug)]
+pub struct PollTallyInfo {
+    pub kind: PollKind,
+    pub counting_method: CountingMethod,
+    pub option_count: i16,
+    pub max_score: Option<i16>,
+    pub seats: Option<i16>,
+    pub quorum: Quorum,
+    pub threshold: Threshold,
+    /// Group members at the time the poll opened (or now, if it has not yet)
+    pub eligible_voters: i32,
+}
+
+#[derive(Clone, Debug)]
+pub struct PollBallot {
+    pub choices: Vec<i16>,
+    /// The voter and the members who delegated to them
+    pub voters: i32,
+    /// Sum of the weights of those members
+    pub weight: i64,
+}
+
 impl DbDriver {
 
     pub async fn new() -> Self {
@@ -59,7 +133,13 @@ impl DbDriver {
             )
             .unwrap();
 
-        Self { db_pool }
+        Self { db_pool, passwords: Passwords::from_env(), session_timeouts: SessionTimeouts::from_env() }
+    }
+
+    /// The timeouts in seconds, as the pgsql functions take them
+    fn session_timeout_args(&self) -> (i32, i32) {
+        let secs = |timeout: Duration| i32::try_from(timeout.as_secs()).expect("Checked by SessionTimeouts::from_env");
+        (secs(self.session_timeouts.idle), secs(self.session_timeouts.max_age))
     }
 
     async fn prepare_pool_query(&self, query: &'static str) -> DbResult<(Object, Statement)> {
@@ -67,7 +147,7 @@ impl DbDriver {
         let stmt = client
             .prepare_cached(query)
             .await
-            .map_err(DbError::Postgres)?;
+            .map_err(DbError::from)?;
 
         Ok((client, stmt))
     }
@@ -82,7 +162,7 @@ impl DbDriver {
         client
             .query_opt(&stmt, args)
             .await
-            .map_err(DbError::Postgres)
+            .map_err(DbError::from)
     }
 
     async fn query_vector(
@@ -95,7 +175,7 @@ impl DbDriver {
         client
             .query(&stmt, args)
             .await
-            .map_err(DbError::Postgres)
+            .map_err(DbError::from)
     }
 
     pub async fn get_session_user(&self, session_id: Uuid) -> DbResult<Option<User>> {
@@ -104,6 +184,7 @@ impl DbDriver {
             "get_session_user",
             [&session_id],
             User { 
+                session_id,
                 user_id, 
                 data: UserData { 
                     user_name, 
@@ -113,12 +194,111 @@ impl DbDriver {
         )
     }
 
-    pub async fn try_login(&self, username: &str, password: &str) -> DbResult<Option<Uuid>> {
-        pg_fn_option!(self, "try_login", [&username, &password])
+    pub async fn unlock_user_login(&self, admin_id: UserId, user_id: UserId) -> DbResult<()> {
+        pg_fn_exec!(self, "unlock_user_login", [&admin_id, &user_id])
+    }
+
+    /// Deletes the login failures which do not count any more
+    pub async fn purge_login_failures(&self) -> DbResult<i32> {
+        pg_fn_one!(self, "purge_login_failures", [])
+    }
+
+    pub async fn renew_session(&self, session_id: Uuid) -> DbResult<()> {
+        let (idle, max_age) = self.session_timeout_args();
+        pg_fn_exec!(self, "renew_session", [&session_id, &idle, &max_age])
+    }
+
+    /// Deletes the expired sessions of all the users
+    pub async fn purge_expired_sessions(&self) -> DbResult<i32> {
+        pg_fn_one!(self, "purge_expired_sessions", [])
+    }
+
+    pub async fn delete_session(&self, session_id: Uuid) -> DbResult<()> {
+        pg_fn_exec!(self, "delete_session", [&session_id])
     }
 
+    pub async fn get_user_sessions(&self, user_id: UserId, session_id: Uuid) -> DbResult<Vec<Session>> {
+        pg_fn_vector!(
+            self,
+            "get_user_sessions",
+            [&user_id, &session_id],
+            ("public_id", SessionInfo { created_at, expires, current })
+        )
+    }
+
+    pub async fn revoke_session(&self, user_id: UserId, session: SessionId) -> DbResult<()> {
+        pg_fn_exec!(self, "revoke_session", [&user_id, &session])
+    }
+
+    /// The password is verified here, rather than in the database,
+    /// and rehashed if stored in plain text or with outdated parameters.
+    /// The failures are recorded per user name and per client address.
+    pub async fn try_login(&self, username: &str, password: &str, address: &str) -> DbResult<Option<Uuid>> {
+        // counted as a failure until the password is verified
+        let retry_after: i32 = pg_fn_one!(self, "claim_login_attempt", [&username, &address])?;
+        if retry_after > 0 {
+            return Err(DbError::TooManyAttempts(retry_after));
+        }
+
+        let credentials: Option<Credentials> = pg_fn_option!(
+            self,
+            "get_user_credentials",
+            [&username],
+            Credentials { user_id, password }
+        )?;
+        // an unknown user name is verified against a dummy hash, not to be told by the timing
+        let (user_id, stored) = match credentials {
+            Some(Credentials { user_id, password }) => (Some(user_id), password),
+            None => (None, self.passwords.dummy_hash().to_owned()),
+        };
+
+        let passwords = self.passwords.clone();
+        let password = password.to_owned();
+        let (verified, rehash) = actix_web::rt::task::spawn_blocking(move || {
+            match passwords.verify(&password, &stored) {
+                Verified::No => (false, None),
+                Verified::Yes => (true, None),
+                Verified::NeedsRehash => (true, Some(passwords.hash(&password))),
+            }
+        })
+        .await
+        .expect("Password verification panicked");
+
+        let Some(user_id) = user_id.filter(|_| verified) else {
+            return Ok(None);
+        };
+
+        pg_fn_exec!(self, "clear_login_failures", [&username, &address])?;
+
+        if let Some(hash) = rehash {
+            pg_fn_exec!(self, "set_user_password", [&user_id, &hash])?;
+        }
+
+        let (idle, max_age) = self.session_timeout_args();
+        pg_fn_one!(self, "add_session", [&user_id, &idle, &max_age]).map(Some)
+    }
+
+    /// A taken name is turned down before the hashing, so that only a new user
+    /// costs one. The name can still be taken meanwhile, which the database tells.
     pub async fn try_register_login( &self, username: &str, password: &str) -> DbResult<Option<Uuid>> {
-        pg_fn_option!(self, "try_register_login", [&username, &password])
+        let taken: Option<Credentials> = pg_fn_option!(
+            self,
+            "get_user_credentials",
+            [&username],
+            Credentials { user_id, password }
+        )?;
+        if taken.is_some() {
+            return Ok(None);
+        }
+
+        let passwords = self.passwords.clone();
+        let password = password.to_owned();
+        let hash = actix_web::rt::task::spawn_blocking(move || passwords.hash(&password))
+            .await
+            .expect("Password hashing panicked");
+
+        let (idle, max_age) = self.session_timeout_args();
+        pg_fn_option!(self, "try_register_login", [&username, &hash, &idle, &max_age])
     }
 
     pub async fn get_assigned_usergroups(&self, user_id: UserId) -> DbResult<Vec<Usergroup>> {
@@ -128,12 +308,404 @@ impl DbDriver {
             [&user_id], 
             (
                 "usergroup_id",
-                UsergroupData { title }
+                UsergroupData { title, description, parent, include_subgroups, tags, public, deleted_at }
             )
         )
     }
 
     pub async fn create_usergroup(&self, creator: UserId, group: UsergroupData) -> DbResult<UsergroupId> {
-        pg_fn_one!(self, "create_assign_usergroup", [&creator, &group.title])
+        pg_fn_one!(
+            self,
+            "create_assign_usergroup",
+            [&creator, &group.title, &group.description, &group.parent, &group.include_subgroups, &group.tags, &group.public]
+        )
+    }
+
+    pub async fn update_usergroup(&self, user_id: UserId, group_id: UsergroupId, group: UsergroupData) -> DbResult<()> {
+        pg_fn_exec!(
+            self,
+            "update_usergroup",
+            [&user_id, &group_id, &group.title, &group.description, &group.include_subgroups, &group.tags, &group.public]
+        )
+    }
+
+    pub async fn delete_usergroup(&self, user_id: UserId, group_id: UsergroupId) -> DbResult<()> {
+        pg_fn_exec!(self, "delete_usergroup", [&user_id, &group_id])
+    }
+
+    pub async fn restore_usergroup(&self, user_id: UserId, group_id: UsergroupId) -> DbResult<()> {
+        pg_fn_exec!(self, "restore_usergroup", [&user_id, &group_id])
+    }
+
+    pub async fn purge_usergroup(&self, user_id: UserId, group_id: UsergroupId) -> DbResult<()> {
+        pg_fn_exec!(self, "purge_usergroup", [&user_id, &group_id])
+    }
+
+    pub async fn get_usergroup_subgroups(&self, user_id: UserId, group_id: UsergroupId) -> DbResult<Vec<Usergroup>> {
+        pg_fn_vector!(
+            self,
+            "get_usergroup_subgroups",
+            [&user_id, &group_id],
+            (
+                "usergroup_id",
+                UsergroupData { title, description, parent, include_subgroups, tags, public, deleted_at }
+            )
+        )
+    }
+
+    pub async fn set_usergroup_parent(&self, user_id: UserId, group_id: UsergroupId, parent: Option<UsergroupId>) -> DbResult<()> {
+        pg_fn_exec!(self, "set_usergroup_parent", [&user_id, &group_id, &parent])
+    }
+
+    pub async fn set_usergroup_include_subgroups(&self, user_id: UserId, group_id: UsergroupId, include: bool) -> DbResult<()> {
+        pg_fn_exec!(self, "set_usergroup_include_subgroups", [&user_id, &group_id, &include])
+    }
+
+    pub async fn set_usergroup_tags(&self, user_id: UserId, group_id: UsergroupId, tags: Vec<String>) -> DbResult<()> {
+        pg_fn_exec!(self, "set_usergroup_tags", [&user_id, &group_id, &tags])
+    }
+
+    pub async fn set_usergroup_public(&self, user_id: UserId, group_id: UsergroupId, public: bool) -> DbResult<()> {
+        pg_fn_exec!(self, "set_usergroup_public", [&user_id, &group_id, &public])
+    }
+
+    pub async fn search_usergroups(&self, query: Option<String>, tag: Option<String>) -> DbResult<Vec<Usergroup>> {
+        pg_fn_vector!(
+            self,
+            "search_usergroups",
+            [&query, &tag],
+            (
+                "usergroup_id",
+                UsergroupData { title, description, parent, include_subgroups, tags, public, deleted_at }
+            )
+        )
+    }
+
+    pub async fn request_join_usergroup(&self, user_id: UserId, group_id: UsergroupId) -> DbResult<()> {
+        pg_fn_exec!(self, "request_join_usergroup", [&user_id, &group_id])
+    }
+
+    pub async fn get_join_requests(&self, user_id: UserId, group_id: UsergroupId) -> DbResult<Vec<JoinRequest>> {
+        pg_fn_vector!(
+            self,
+            "get_join_requests",
+            [&user_id, &group_id],
+            (
+                "user_id",
+                JoinRequestInfo {
+                    user: UserData { user_name, full_name },
+                    created_at
+                }
+            )
+        )
+    }
+
+    pub async fn answer_join_request(&self, user_id: UserId, group_id: UsergroupId, requester_id: UserId, accept: bool) -> DbResult<()> {
+        pg_fn_exec!(self, "answer_join_request", [&user_id, &group_id, &requester_id, &accept])
+    }
+
+    //---------------------------------------------------------------
+
+    pub async fn create_poll(&self, author: UserId, group_id: UsergroupId, poll: PollData) -> DbResult<PollId> {
+        pg_fn_one!(
+            self,
+            "create_poll",
+            [
+                &author, &group_id, &poll.question, &poll.options, &poll.kind,
+                &poll.counting_method, &poll.max_score, &poll.seats, &poll.secret, &poll.hide_results,
+                &poll.quorum.min_voters, &poll.quorum.min_percent,
+                &poll.threshold.numerator, &poll.threshold.denominator,
+                &poll.opens_at, &poll.closes_at, &poll.option_bodies
+            ]
+        )
+    }
+
+    pub async fn update_poll(&self, user_id: UserId, poll_id: PollId, poll: PollData) -> DbResult<()> {
+        pg_fn_exec!(
+            self,
+            "update_poll",
+            [
+                &user_id, &poll_id, &poll.question, &poll.options, &poll.kind,
+                &poll.counting_method, &poll.max_score, &poll.seats, &poll.secret, &poll.hide_results,
+                &poll.quorum.min_voters, &poll.quorum.min_percent,
+                &poll.threshold.numerator, &poll.threshold.denominator,
+                &poll.opens_at, &poll.closes_at, &poll.option_bodies
+            ]
+        )
+    }
+
+    pub async fn set_poll_state(&self, user_id: UserId, poll_id: PollId, state: PollState) -> DbResult<()> {
+        pg_fn_exec!(self, "set_poll_state", [&user_id, &poll_id, &state])
+    }
+
+    /// Opens and closes the polls which are due, returns the number of changed polls
+    pub async fn advance_poll_states(&self) -> DbResult<i32> {
+        pg_fn_one!(self, "advance_poll_states", [])
+    }
+
+    pub async fn get_usergroup_polls(&self, user_id: UserId, group_id: UsergroupId) -> DbResult<Vec<Poll>> {
+        pg_fn_vector!(
+            self,
+            "get_usergroup_polls",
+            [&user_id, &group_id],
+            (
+                "poll_id",
+                PollInfo {
+                    author_id,
+                    state,
+                    data: PollData {
+                        question,
+                        options,
+                        option_bodies,
+                        kind,
+                        counting_method,
+                        max_score,
+                        seats,
+                        secret,
+                        hide_results,
+                        quorum: Quorum { min_voters<"quorum_voters">, min_percent<"quorum_percent"> },
+                        threshold: Threshold { numerator<"threshold_num">, denominator<"threshold_den"> },
+                        opens_at,
+                        closes_at
+                    }
+                }
+            )
+        )
+    }
+
+    pub async fn get_usergroup_poll(&self, user_id: UserId, group_id: UsergroupId, poll_id: PollId) -> DbResult<Option<PollInfo>> {
+        pg_fn_option!(
+            self,
+            "get_usergroup_poll",
+            [&user_id, &group_id, &poll_id],
+            PollInfo {
+                author_id,
+                state,
+                data: PollData {
+                    question,
+                    options,
+                    option_bodies,
+                    kind,
+                    counting_method,
+                    max_score,
+                    seats,
+                    secret,
+                    hide_results,
+                    quorum: Quorum { min_voters<"quorum_voters">, min_percent<"quorum_percent"> },
+                    threshold: Threshold { numerator<"threshold_num">, denominator<"threshold_den"> },
+                    opens_at,
+                    closes_at
+                }
+            }
+        )
+    }
+
+    pub async fn cast_vote(&self, user_id: UserId, poll_id: PollId, ballot: Ballot) -> DbResult<VoteReceipt> {
+        pg_fn_one!(
+            self,
+            "cast_vote",
+            [&user_id, &poll_id, &ballot.kind(), &ballot.choices()],
+            VoteReceipt { commitment, nonce, replaced }
+        )
+    }
+
+    pub async fn get_poll_receipts(&self, user_id: UserId, poll_id: PollId) -> DbResult<Vec<String>> {
+        pg_fn_vector!(self, "get_poll_receipts", [&user_id, &poll_id])
+    }
+
+    pub async fn get_poll_tally_info(&self, user_id: UserId, poll_id: PollId) -> DbResult<PollTallyInfo> {
+        pg_fn_one!(
+            self,
+            "get_poll_tally_info",
+            [&user_id, &poll_id],
+            PollTallyInfo {
+                kind,
+                counting_method,
+                option_count,
+                max_score,
+                seats,
+                quorum: Quorum { min_voters<"quorum_voters">, min_percent<"quorum_percent"> },
+                threshold: Threshold { numerator<"threshold_num">, denominator<"threshold_den"> },
+                eligible_voters
+            }
+        )
+    }
+
+    pub async fn get_poll_ballots(&self, user_id: UserId, poll_id: PollId) -> DbResult<Vec<PollBallot>> {
+        pg_fn_vector!(
+            self,
+            "get_poll_ballots",
+            [&user_id, &poll_id],
+            PollBallot { choices, voters, weight }
+        )
+    }
+
+    //---------------------------------------------------------------
+
+    pub async fn get_delegations(&self, user_id: UserId, group_id: UsergroupId) -> DbResult<Vec<Delegation>> {
+        pg_fn_vector!(
+            self,
+            "get_delegations",
+            [&user_id, &group_id],
+            Delegation { poll_id, delegate_id }
+        )
+    }
+
+    pub async fn get_usergroup_members(&self, user_id: UserId, group_id: UsergroupId) -> DbResult<Vec<Member>> {
+        pg_fn_vector!(
+            self,
+            "get_usergroup_members",
+            [&user_id, &group_id],
+            (
+                "user_id",
+                MemberInfo {
+                    user: UserData { user_name, full_name },
+                    role,
+                    weight,
+                    inherited
+                }
+            )
+        )
+    }
+
+    pub async fn remove_usergroup_member(&self, user_id: UserId, group_id: UsergroupId, member_id: UserId) -> DbResult<()> {
+        pg_fn_exec!(self, "remove_usergroup_member", [&user_id, &group_id, &member_id])
+    }
+
+    pub async fn leave_usergroup(&self, user_id: UserId, group_id: UsergroupId) -> DbResult<()> {
+        pg_fn_exec!(self, "leave_usergroup", [&user_id, &group_id])
+    }
+
+    pub async fn set_member_weight(&self, user_id: UserId, group_id: UsergroupId, member_id: UserId, weight: i32) -> DbResult<()> {
+        pg_fn_exec!(self, "set_member_weight", [&user_id, &group_id, &member_id, &weight])
+    }
+
+    pub async fn set_member_role(&self, user_id: UserId, group_id: UsergroupId, member_id: UserId, role: Role) -> DbResult<()> {
+        pg_fn_exec!(self, "set_member_role", [&user_id, &group_id, &member_id, &role])
+    }
+
+    pub async fn create_invite(&self, user_id: UserId, group_id: UsergroupId, invite: InviteData) -> DbResult<String> {
+        pg_fn_one!(self, "create_invite", [&user_id, &group_id, &invite.expires_at, &invite.max_uses, &invite.role])
+    }
+
+    pub async fn get_usergroup_invites(&self, user_id: UserId, group_id: UsergroupId) -> DbResult<Vec<Invite>> {
+        pg_fn_vector!(
+            self,
+            "get_usergroup_invites",
+            [&user_id, &group_id],
+            (
+                "invite_id",
+                InviteInfo {
+                    code,
+                    created_by,
+                    created_at,
+                    uses,
+                    data: InviteData { expires_at, max_uses, role }
+                }
+            )
+        )
+    }
+
+    pub async fn revoke_invite(&self, user_id: UserId, invite_id: InviteId) -> DbResult<()> {
+        pg_fn_exec!(self, "revoke_invite", [&user_id, &invite_id])
+    }
+
+    pub async fn redeem_invite(&self, user_id: UserId, code: String) -> DbResult<UsergroupId> {
+        pg_fn_one!(self, "redeem_invite", [&user_id, &code])
+    }
+
+    pub async fn set_delegation(&self, user_id: UserId, group_id: UsergroupId, delegation: Delegation) -> DbResult<()> {
+        pg_fn_exec!(self, "set_delegation", [&user_id, &group_id, &delegation.poll_id, &delegation.delegate_id])
+    }
+
+    pub async fn revoke_delegation(&self, user_id: UserId, group_id: UsergroupId, poll_id: Option<PollId>) -> DbResult<()> {
+        pg_fn_exec!(self, "revoke_delegation", [&user_id, &group_id, &poll_id])
+    }
+
+    //---------------------------------------------------------------
+
+    pub async fn create_proposal(&self, user_id: UserId, group_id: UsergroupId, proposal: ProposalData) -> DbResult<ProposalId> {
+        pg_fn_one!(self, "create_proposal", [&user_id, &group_id, &proposal.title, &proposal.body])
+    }
+
+    pub async fn get_usergroup_proposals(&self, user_id: UserId, group_id: UsergroupId) -> DbResult<Vec<Proposal>> {
+        pg_fn_vector!(
+            self,
+            "get_usergroup_proposals",
+            [&user_id, &group_id],
+            (
+                "proposal_id",
+                ProposalInfo {
+                    author_id,
+                    created_at,
+                    poll_id,
+                    data: ProposalData { title, body }
+                }
+            )
+        )
+    }
+
+    pub async fn get_proposal(&self, user_id: UserId, proposal_id: ProposalId) -> DbResult<ProposalInfo> {
+        pg_fn_one!(
+            self,
+            "get_proposal",
+            [&user_id, &proposal_id],
+            ProposalInfo {
+                author_id,
+                created_at,
+                poll_id,
+                data: ProposalData { title, body }
+            }
+        )
+    }
+
+    pub async fn add_proposal_comment(&self, user_id: UserId, proposal_id: ProposalId, comment: CommentData) -> DbResult<CommentId> {
+        pg_fn_one!(self, "add_proposal_comment", [&user_id, &proposal_id, &comment.parent_id, &comment.body])
+    }
+
+    pub async fn get_proposal_comments(&self, user_id: UserId, proposal_id: ProposalId) -> DbResult<Vec<Comment>> {
+        pg_fn_vector!(
+            self,
+            "get_proposal_comments",
+            [&user_id, &proposal_id],
+            (
+                "comment_id",
+                CommentInfo {
+                    author_id,
+                    created_at,
+                    data: CommentData { parent_id, body }
+                }
+            )
+        )
+    }
+
+    pub async fn add_amendment(&self, user_id: UserId, proposal_id: ProposalId, amendment: AmendmentData) -> DbResult<AmendmentId> {
+        pg_fn_one!(self, "add_amendment", [&user_id, &proposal_id, &amendment.title, &amendment.body])
+    }
+
+    pub async fn get_amendments(&self, user_id: UserId, proposal_id: ProposalId) -> DbResult<Vec<Amendment>> {
+        pg_fn_vector!(
+            self,
+            "get_amendments",
+            [&user_id, &proposal_id],
+            (
+                "amendment_id",
+                AmendmentInfo {
+                    author_id,
+                    state,
+                    created_at,
+                    data: AmendmentData { title, body }
+                }
+            )
+        )
+    }
+
+    pub async fn set_amendment_state(&self, user_id: UserId, amendment_id: AmendmentId, state: AmendmentState) -> DbResult<()> {
+        pg_fn_exec!(self, "set_amendment_state", [&user_id, &amendment_id, &state])
+    }
+
+    /// Creates a draft poll out of the proposal and its accepted amendments
+    pub async fn promote_proposal(&self, user_id: UserId, proposal_id: ProposalId) -> DbResult<PollId> {
+        pg_fn_one!(self, "promote_proposal", [&user_id, &proposal_id])
     }
 }
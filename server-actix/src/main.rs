@@ -1,17 +1,34 @@
-use actix_web::{cookie, get, post, web, App, HttpRequest, HttpResponse, HttpServer, Responder};
+use actix_web::{cookie, delete, get, post, put, web, App, HttpRequest, HttpResponse, HttpServer, Responder};
+use actix_web::http::header;
 
+mod background;
+mod ballot;
+mod csrf;
 mod db_driver;
+mod password;
+mod proxy;
 mod session;
+mod tally;
 
 use db_driver::{DbDriver, User, DbError};
 
-use plebiscite_types::{LoginInfo, UsergroupData};
+use plebiscite_types::proposal::{ProposalData, ProposalId, CommentData, AmendmentData, AmendmentId, AmendmentState};
+use plebiscite_types::{LoginInfo, SessionId, UserId, UsergroupData, UsergroupId, PollData, PollId, PollState, Ballot, CountingMethod, Delegation, Role, InviteData, InviteId};
 
 //----------------------------------------------------------------
 
 impl actix_web::error::ResponseError for DbError {
     fn error_response(&self) -> HttpResponse {
-        HttpResponse::ServiceUnavailable().body("Database error")
+        match self {
+            DbError::Forbidden(msg) => HttpResponse::Forbidden().body(msg.clone()),
+            DbError::NotFound(msg) => HttpResponse::NotFound().body(msg.clone()),
+            DbError::Conflict(msg) => HttpResponse::Conflict().body(msg.clone()),
+            DbError::Invalid(msg) => HttpResponse::BadRequest().body(msg.clone()),
+            DbError::TooManyAttempts(secs) => HttpResponse::TooManyRequests()
+                .insert_header((header::RETRY_AFTER, secs.to_string()))
+                .body("Too many failed logins"),
+            _ => HttpResponse::ServiceUnavailable().body("Database error"),
+        }
     }
 }
 
@@ -20,10 +37,17 @@ impl actix_web::error::ResponseError for DbError {
 async fn main() -> std::io::Result<()> {
     let drv = db_driver::DbDriver::new().await;
     let app_data = web::Data::new(drv.clone());
+    let proxy = web::Data::new(proxy::TrustedProxy::from_env());
+
+    background::spawn_poll_state_updates(drv.clone());
+    background::spawn_session_purge(drv.clone());
+    background::spawn_login_failures_purge(drv.clone());
 
     HttpServer::new(move || {
         App::new()
+            .wrap(csrf::CsrfMiddlewareFactory::new())
             .app_data(app_data.clone())
+            .app_data(proxy.clone())
             .service(static_file)
             .service(page_spa_main)
             .service(page_login)
@@ -32,9 +56,8 @@ async fn main() -> std::io::Result<()> {
             .service(
                 web::scope("/api")
                     .wrap(session::SessionMiddlewareFactory::new(drv.clone()))
-                    .service(current_user)
-                    .service(user_groups)
-                    .service(user_group_create)
+                    .wrap(csrf::CsrfMiddlewareFactory::with_token())
+                    .configure(api_services)
             )
     })
     .bind("127.0.0.1:8080")?
@@ -42,6 +65,59 @@ async fn main() -> std::io::Result<()> {
     .await
 }
 
+/// The services of the `/api` scope, for the logged in users
+fn api_services(cfg: &mut web::ServiceConfig) {
+    cfg
+        .service(current_user)
+        .service(api_logout)
+        .service(user_sessions)
+        .service(user_session_revoke)
+        .service(user_unlock)
+        .service(user_groups)
+        .service(user_group_create)
+        .service(group_polls)
+        .service(group_poll_create)
+        .service(group_poll)
+        .service(group_search)
+        .service(group_update)
+        .service(group_delete)
+        .service(group_restore)
+        .service(group_subgroups)
+        .service(group_parent_set)
+        .service(group_include_subgroups_set)
+        .service(group_tags_set)
+        .service(group_public_set)
+        .service(group_join_requests)
+        .service(group_join_request_add)
+        .service(group_join_request_answer)
+        .service(group_members)
+        .service(group_member_remove)
+        .service(group_leave)
+        .service(group_member_role_set)
+        .service(group_member_weight_set)
+        .service(group_delegations)
+        .service(group_delegation_set)
+        .service(group_delegation_revoke)
+        .service(group_invites)
+        .service(group_invite_create)
+        .service(invite_revoke)
+        .service(invite_redeem)
+        .service(poll_update)
+        .service(poll_state_set)
+        .service(poll_vote)
+        .service(poll_receipts)
+        .service(poll_results)
+        .service(group_proposals)
+        .service(group_proposal_create)
+        .service(proposal_details)
+        .service(proposal_comments)
+        .service(proposal_comment_add)
+        .service(proposal_amendments)
+        .service(proposal_amendment_add)
+        .service(amendment_state_set)
+        .service(proposal_promote);
+}
+
 #[get("/{filepath:.+\\.*(js|wasm)}")]
 async fn static_file(filepath: web::Path<String>) -> actix_web::Result<actix_files::NamedFile> {
     let newpath = format!("server_root/{}", filepath);
@@ -99,6 +175,7 @@ fn login_with_cookie(req: HttpRequest, session_id: Option<uuid::Uuid>) -> HttpRe
             .path("/")
             //.secure(true)
             .http_only(true)
+            .same_site(cookie::SameSite::Lax)
             .finish();
         HttpResponse::Ok()
             .cookie(cookie)
@@ -113,11 +190,13 @@ fn login_with_cookie(req: HttpRequest, session_id: Option<uuid::Uuid>) -> HttpRe
 async fn api_login(
     req: HttpRequest,
     drv: web::Data<DbDriver>,
+    proxy: web::Data<proxy::TrustedProxy>,
     form: web::Json<LoginInfo>,
 ) -> impl Responder {
-    println!("trying to login as {}, {}", form.username, form.password);
+    println!("trying to login as {}", form.username);
+    let address = proxy.client_address(&req);
     drv.get_ref()
-        .try_login(&form.username, &form.password)
+        .try_login(&form.username, &form.password, &address)
         .await
         .map(|session_id| login_with_cookie(req, session_id))
 }
@@ -128,7 +207,7 @@ async fn api_register_login(
     drv: web::Data<DbDriver>,
     form: actix_web::web::Json<LoginInfo>,
 ) -> impl Responder {
-    println!("trying to register as {}, {}", form.username, form.password);
+    println!("trying to register as {}", form.username);
     drv.get_ref()
         .try_register_login(&form.username, &form.password)
         .await
@@ -145,6 +224,18 @@ macro_rules! respond_ok_json {
     };
 }
 
+macro_rules! respond_opt_json {
+    ($drv:ident, $fn:ident ($($args:expr),+)) => {
+        $drv.get_ref()
+            .$fn($($args),+)
+            .await
+            .map(|result| match result {
+                Some(result) => HttpResponse::Ok().json(result),
+                None => HttpResponse::NotFound().finish(),
+            })
+    };
+}
+
 macro_rules! respond_ok_text {
     ($drv:ident, $fn:ident ($($args:expr),+) $(-> $($cont:tt)+)?) => {
         $drv.get_ref()
@@ -154,6 +245,33 @@ macro_rules! respond_ok_text {
     };
 }
 
+/// Ends the current session and clears its cookie
+#[post("/logout")]
+async fn api_logout(user: User, drv: web::Data<DbDriver>) -> Result<HttpResponse, DbError> {
+    drv.get_ref().delete_session(user.session_id).await?;
+
+    let mut cookie = cookie::Cookie::build(session::SESSION_ID, "").path("/").finish();
+    cookie.make_removal();
+    Ok(HttpResponse::Ok().cookie(cookie).json(()))
+}
+
+#[get("/sessions")]
+async fn user_sessions(user: User, drv: web::Data<DbDriver>) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, get_user_sessions(user.user_id, user.session_id))
+}
+
+/// Logs out one of the user's sessions, the current one included
+#[delete("/sessions/{session_id}")]
+async fn user_session_revoke(user: User, drv: web::Data<DbDriver>, session: web::Path<SessionId>) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, revoke_session(user.user_id, session.into_inner()))
+}
+
+/// Lifts the lockout after failed logins, for the site admins
+#[post("/users/{user_id}/unlock")]
+async fn user_unlock(user: User, drv: web::Data<DbDriver>, user_id: web::Path<UserId>) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, unlock_user_login(user.user_id, user_id.into_inner()))
+}
+
 #[get("/current_user")]
 async fn current_user(user: User) -> impl Responder {
     HttpResponse::Ok().body(user.data.user_name)
@@ -172,3 +290,553 @@ async fn user_group_create(user: User, drv: web::Data<DbDriver>, web::Json(group
     respond_ok_json!(drv, create_usergroup(user.user_id, group))
     //respond_ok_text!(drv, create_usergroup(user.user_id, group) -> value.to_string())
 }
+
+#[get("/groups/{group_id}/polls")]
+async fn group_polls(user: User, drv: web::Data<DbDriver>, group_id: web::Path<UsergroupId>) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, get_usergroup_polls(user.user_id, group_id.into_inner()))
+}
+
+#[post("/groups/{group_id}/polls")]
+async fn group_poll_create(
+    user: User,
+    drv: web::Data<DbDriver>,
+    group_id: web::Path<UsergroupId>,
+    web::Json(poll): web::Json<PollData>,
+) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, create_poll(user.user_id, group_id.into_inner(), poll))
+}
+
+#[get("/groups/{group_id}/polls/{poll_id}")]
+async fn group_poll(user: User, drv: web::Data<DbDriver>, path: web::Path<(UsergroupId, PollId)>) -> Result<HttpResponse, DbError> {
+    let (group_id, poll_id) = path.into_inner();
+    respond_opt_json!(drv, get_usergroup_poll(user.user_id, group_id, poll_id))
+}
+
+#[derive(serde::Deserialize)]
+struct SearchQuery {
+    /// Words to find in the title
+    q: Option<String>,
+    tag: Option<String>,
+}
+
+/// The directory of the public groups
+#[get("/groups/search")]
+async fn group_search(_user: User, drv: web::Data<DbDriver>, query: web::Query<SearchQuery>) -> Result<HttpResponse, DbError> {
+    let SearchQuery { q, tag } = query.into_inner();
+    respond_ok_json!(drv, search_usergroups(q, tag))
+}
+
+/// Admins only, the parent is changed with `/groups/{group_id}/parent` and the deletion is ignored
+#[put("/groups/{group_id}")]
+async fn group_update(
+    user: User,
+    drv: web::Data<DbDriver>,
+    group_id: web::Path<UsergroupId>,
+    web::Json(group): web::Json<UsergroupData>,
+) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, update_usergroup(user.user_id, group_id.into_inner(), group))
+}
+
+#[derive(serde::Deserialize)]
+struct DeleteQuery {
+    /// Delete the group for good, with its polls, votes and proposals
+    #[serde(default)]
+    permanent: bool,
+}
+
+/// Owners only, hides the group until it gets restored, unless `permanent` is set
+#[delete("/groups/{group_id}")]
+async fn group_delete(
+    user: User,
+    drv: web::Data<DbDriver>,
+    group_id: web::Path<UsergroupId>,
+    query: web::Query<DeleteQuery>,
+) -> Result<HttpResponse, DbError> {
+    if query.permanent {
+        respond_ok_json!(drv, purge_usergroup(user.user_id, group_id.into_inner()))
+    } else {
+        respond_ok_json!(drv, delete_usergroup(user.user_id, group_id.into_inner()))
+    }
+}
+
+/// Owners only
+#[post("/groups/{group_id}/restore")]
+async fn group_restore(user: User, drv: web::Data<DbDriver>, group_id: web::Path<UsergroupId>) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, restore_usergroup(user.user_id, group_id.into_inner()))
+}
+
+/// The whole tree under the group, each subgroup after its parent
+#[get("/groups/{group_id}/subgroups")]
+async fn group_subgroups(user: User, drv: web::Data<DbDriver>, group_id: web::Path<UsergroupId>) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, get_usergroup_subgroups(user.user_id, group_id.into_inner()))
+}
+
+/// Owners only, the body is the new parent (the user must be its admin), `null` for the top of the tree
+#[post("/groups/{group_id}/parent")]
+async fn group_parent_set(
+    user: User,
+    drv: web::Data<DbDriver>,
+    group_id: web::Path<UsergroupId>,
+    web::Json(parent): web::Json<Option<UsergroupId>>,
+) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, set_usergroup_parent(user.user_id, group_id.into_inner(), parent))
+}
+
+/// Admins only
+#[post("/groups/{group_id}/include_subgroups")]
+async fn group_include_subgroups_set(
+    user: User,
+    drv: web::Data<DbDriver>,
+    group_id: web::Path<UsergroupId>,
+    web::Json(include): web::Json<bool>,
+) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, set_usergroup_include_subgroups(user.user_id, group_id.into_inner(), include))
+}
+
+/// Admins only
+#[post("/groups/{group_id}/tags")]
+async fn group_tags_set(
+    user: User,
+    drv: web::Data<DbDriver>,
+    group_id: web::Path<UsergroupId>,
+    web::Json(tags): web::Json<Vec<String>>,
+) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, set_usergroup_tags(user.user_id, group_id.into_inner(), tags))
+}
+
+/// Admins only, making a group private drops its join requests
+#[post("/groups/{group_id}/public")]
+async fn group_public_set(
+    user: User,
+    drv: web::Data<DbDriver>,
+    group_id: web::Path<UsergroupId>,
+    web::Json(public): web::Json<bool>,
+) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, set_usergroup_public(user.user_id, group_id.into_inner(), public))
+}
+
+/// Admins only
+#[get("/groups/{group_id}/join_requests")]
+async fn group_join_requests(user: User, drv: web::Data<DbDriver>, group_id: web::Path<UsergroupId>) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, get_join_requests(user.user_id, group_id.into_inner()))
+}
+
+/// Asks to join a public group
+#[post("/groups/{group_id}/join_requests")]
+async fn group_join_request_add(user: User, drv: web::Data<DbDriver>, group_id: web::Path<UsergroupId>) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, request_join_usergroup(user.user_id, group_id.into_inner()))
+}
+
+/// Admins only, the body tells whether the request is accepted
+#[post("/groups/{group_id}/join_requests/{user_id}")]
+async fn group_join_request_answer(
+    user: User,
+    drv: web::Data<DbDriver>,
+    path: web::Path<(UsergroupId, UserId)>,
+    web::Json(accept): web::Json<bool>,
+) -> Result<HttpResponse, DbError> {
+    let (group_id, requester_id) = path.into_inner();
+    respond_ok_json!(drv, answer_join_request(user.user_id, group_id, requester_id, accept))
+}
+
+#[get("/groups/{group_id}/members")]
+async fn group_members(user: User, drv: web::Data<DbDriver>, group_id: web::Path<UsergroupId>) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, get_usergroup_members(user.user_id, group_id.into_inner()))
+}
+
+/// Admins only, the owners and admins are removed by the owners
+#[delete("/groups/{group_id}/members/{user_id}")]
+async fn group_member_remove(
+    user: User,
+    drv: web::Data<DbDriver>,
+    path: web::Path<(UsergroupId, UserId)>,
+) -> Result<HttpResponse, DbError> {
+    let (group_id, member_id) = path.into_inner();
+    respond_ok_json!(drv, remove_usergroup_member(user.user_id, group_id, member_id))
+}
+
+/// The last owner cannot leave
+#[post("/groups/{group_id}/leave")]
+async fn group_leave(user: User, drv: web::Data<DbDriver>, group_id: web::Path<UsergroupId>) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, leave_usergroup(user.user_id, group_id.into_inner()))
+}
+
+/// Admins only, the owner and admin roles are managed by the owners
+#[post("/groups/{group_id}/members/{user_id}/role")]
+async fn group_member_role_set(
+    user: User,
+    drv: web::Data<DbDriver>,
+    path: web::Path<(UsergroupId, UserId)>,
+    web::Json(role): web::Json<Role>,
+) -> Result<HttpResponse, DbError> {
+    let (group_id, member_id) = path.into_inner();
+    respond_ok_json!(drv, set_member_role(user.user_id, group_id, member_id, role))
+}
+
+/// Admins only, the body is the member's new weight
+#[post("/groups/{group_id}/members/{user_id}/weight")]
+async fn group_member_weight_set(
+    user: User,
+    drv: web::Data<DbDriver>,
+    path: web::Path<(UsergroupId, UserId)>,
+    web::Json(weight): web::Json<i32>,
+) -> Result<HttpResponse, DbError> {
+    let (group_id, member_id) = path.into_inner();
+    respond_ok_json!(drv, set_member_weight(user.user_id, group_id, member_id, weight))
+}
+
+#[get("/groups/{group_id}/delegations")]
+async fn group_delegations(user: User, drv: web::Data<DbDriver>, group_id: web::Path<UsergroupId>) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, get_delegations(user.user_id, group_id.into_inner()))
+}
+
+#[post("/groups/{group_id}/delegations")]
+async fn group_delegation_set(
+    user: User,
+    drv: web::Data<DbDriver>,
+    group_id: web::Path<UsergroupId>,
+    web::Json(delegation): web::Json<Delegation>,
+) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, set_delegation(user.user_id, group_id.into_inner(), delegation))
+}
+
+/// The body is the poll of the delegation to revoke, `null` for the group-wide one
+#[post("/groups/{group_id}/delegations/revoke")]
+async fn group_delegation_revoke(
+    user: User,
+    drv: web::Data<DbDriver>,
+    group_id: web::Path<UsergroupId>,
+    web::Json(poll_id): web::Json<Option<PollId>>,
+) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, revoke_delegation(user.user_id, group_id.into_inner(), poll_id))
+}
+
+/// Admins only, lists the invites which can still be used
+#[get("/groups/{group_id}/invites")]
+async fn group_invites(user: User, drv: web::Data<DbDriver>, group_id: web::Path<UsergroupId>) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, get_usergroup_invites(user.user_id, group_id.into_inner()))
+}
+
+/// Admins only, responds with the invite code
+#[post("/groups/{group_id}/invites")]
+async fn group_invite_create(
+    user: User,
+    drv: web::Data<DbDriver>,
+    group_id: web::Path<UsergroupId>,
+    web::Json(invite): web::Json<InviteData>,
+) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, create_invite(user.user_id, group_id.into_inner(), invite))
+}
+
+#[post("/invites/{invite_id}/revoke")]
+async fn invite_revoke(user: User, drv: web::Data<DbDriver>, invite_id: web::Path<InviteId>) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, revoke_invite(user.user_id, invite_id.into_inner()))
+}
+
+/// Joins the current user to the invite's group, responds with the group
+#[post("/invites/redeem/{code}")]
+async fn invite_redeem(user: User, drv: web::Data<DbDriver>, code: web::Path<String>) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, redeem_invite(user.user_id, code.into_inner()))
+}
+
+#[put("/polls/{poll_id}")]
+async fn poll_update(
+    user: User,
+    drv: web::Data<DbDriver>,
+    poll_id: web::Path<PollId>,
+    web::Json(poll): web::Json<PollData>,
+) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, update_poll(user.user_id, poll_id.into_inner(), poll))
+}
+
+#[post("/polls/{poll_id}/state")]
+async fn poll_state_set(
+    user: User,
+    drv: web::Data<DbDriver>,
+    poll_id: web::Path<PollId>,
+    web::Json(state): web::Json<PollState>,
+) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, set_poll_state(user.user_id, poll_id.into_inner(), state))
+}
+
+#[post("/polls/{poll_id}/vote")]
+async fn poll_vote(
+    user: User,
+    drv: web::Data<DbDriver>,
+    poll_id: web::Path<PollId>,
+    web::Json(ballot): web::Json<Ballot>,
+) -> Result<HttpResponse, DbError> {
+    let drv = drv.get_ref();
+    let poll_id = poll_id.into_inner();
+
+    let poll = drv.get_poll_tally_info(user.user_id, poll_id).await?;
+    if let Err(msg) = ballot::validate(&ballot, &poll) {
+        return Ok(HttpResponse::BadRequest().body(msg));
+    }
+
+    drv.cast_vote(user.user_id, poll_id, ballot)
+        .await
+        .map(|receipt| HttpResponse::Ok().json(receipt))
+}
+
+#[get("/polls/{poll_id}/receipts")]
+async fn poll_receipts(user: User, drv: web::Data<DbDriver>, poll_id: web::Path<PollId>) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, get_poll_receipts(user.user_id, poll_id.into_inner()))
+}
+
+#[derive(serde::Deserialize)]
+struct ResultsQuery {
+    /// Count ranked ballots by another method than the poll's own one
+    method: Option<CountingMethod>,
+}
+
+#[get("/polls/{poll_id}/results")]
+async fn poll_results(
+    user: User,
+    drv: web::Data<DbDriver>,
+    poll_id: web::Path<PollId>,
+    query: web::Query<ResultsQuery>,
+) -> Result<HttpResponse, DbError> {
+    let drv = drv.get_ref();
+    let poll_id = poll_id.into_inner();
+
+    let poll = drv.get_poll_tally_info(user.user_id, poll_id).await?;
+    let ballots = drv.get_poll_ballots(user.user_id, poll_id).await?;
+
+    let method = query.method.unwrap_or(poll.counting_method);
+    Ok(HttpResponse::Ok().json(tally::count(&poll, method, &ballots)))
+}
+
+//---------- proposals -----------
+
+#[get("/groups/{group_id}/proposals")]
+async fn group_proposals(user: User, drv: web::Data<DbDriver>, group_id: web::Path<UsergroupId>) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, get_usergroup_proposals(user.user_id, group_id.into_inner()))
+}
+
+#[post("/groups/{group_id}/proposals")]
+async fn group_proposal_create(
+    user: User,
+    drv: web::Data<DbDriver>,
+    group_id: web::Path<UsergroupId>,
+    web::Json(proposal): web::Json<ProposalData>,
+) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, create_proposal(user.user_id, group_id.into_inner(), proposal))
+}
+
+#[get("/proposals/{proposal_id}")]
+async fn proposal_details(user: User, drv: web::Data<DbDriver>, proposal_id: web::Path<ProposalId>) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, get_proposal(user.user_id, proposal_id.into_inner()))
+}
+
+#[get("/proposals/{proposal_id}/comments")]
+async fn proposal_comments(user: User, drv: web::Data<DbDriver>, proposal_id: web::Path<ProposalId>) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, get_proposal_comments(user.user_id, proposal_id.into_inner()))
+}
+
+#[post("/proposals/{proposal_id}/comments")]
+async fn proposal_comment_add(
+    user: User,
+    drv: web::Data<DbDriver>,
+    proposal_id: web::Path<ProposalId>,
+    web::Json(comment): web::Json<CommentData>,
+) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, add_proposal_comment(user.user_id, proposal_id.into_inner(), comment))
+}
+
+#[get("/proposals/{proposal_id}/amendments")]
+async fn proposal_amendments(user: User, drv: web::Data<DbDriver>, proposal_id: web::Path<ProposalId>) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, get_amendments(user.user_id, proposal_id.into_inner()))
+}
+
+#[post("/proposals/{proposal_id}/amendments")]
+async fn proposal_amendment_add(
+    user: User,
+    drv: web::Data<DbDriver>,
+    proposal_id: web::Path<ProposalId>,
+    web::Json(amendment): web::Json<AmendmentData>,
+) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, add_amendment(user.user_id, proposal_id.into_inner(), amendment))
+}
+
+#[post("/amendments/{amendment_id}/state")]
+async fn amendment_state_set(
+    user: User,
+    drv: web::Data<DbDriver>,
+    amendment_id: web::Path<AmendmentId>,
+    web::Json(state): web::Json<AmendmentState>,
+) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, set_amendment_state(user.user_id, amendment_id.into_inner(), state))
+}
+
+#[post("/proposals/{proposal_id}/promote")]
+async fn proposal_promote(user: User, drv: web::Data<DbDriver>, proposal_id: web::Path<ProposalId>) -> Result<HttpResponse, DbError> {
+    respond_ok_json!(drv, promote_proposal(user.user_id, proposal_id.into_inner()))
+}
+
+
+//---------- tests -----------
+
+/// Requests by the members lacking the role, against the `pleb` database set up
+/// from db-postgres, which the app's role connects to. Every test has its own
+/// users, named after it, and removes them along with their group in the end.
+/// The app's role cannot delete users, so the rows are removed as `postgres`,
+/// as the db-postgres tests are run.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::test;
+    use plebiscite_types::{InviteData, PollKind};
+
+    const ROLES: [&str; 4] = ["owner", "member", "observer", "outsider"];
+
+    struct Fixture {
+        drv: DbDriver,
+        group_id: UsergroupId,
+        poll_id: PollId,
+        member: (UserId, uuid::Uuid),
+        observer: (UserId, uuid::Uuid),
+        outsider: (UserId, uuid::Uuid),
+    }
+
+    fn user_names(test_name: &str) -> Vec<String> {
+        ROLES.iter().map(|role| format!("test_api_{}_{}", test_name, role)).collect()
+    }
+
+    /// Deletes the users of the test, their sessions and their groups, with the
+    /// polls and the memberships
+    async fn cleanup(test_name: &str) {
+        let (client, connection) = tokio_postgres::connect("host=localhost user=postgres dbname=pleb", tokio_postgres::NoTls)
+            .await
+            .expect("Cannot connect to the pleb database as postgres");
+        actix_web::rt::spawn(connection);
+
+        let names = user_names(test_name);
+        let users = "SELECT user_id FROM users WHERE user_name = ANY($1)";
+        client
+            .execute(&format!("DELETE FROM usergroups WHERE usergroup_id IN (SELECT usergroup_id FROM users_usergroups WHERE user_id IN ({}))", users), &[&names])
+            .await
+            .unwrap();
+        client.execute(&format!("DELETE FROM sessions WHERE user_id IN ({})", users), &[&names]).await.unwrap();
+        client.execute("DELETE FROM users WHERE user_name = ANY($1)", &[&names]).await.unwrap();
+    }
+
+    async fn register(drv: &DbDriver, user_name: &str) -> (UserId, uuid::Uuid) {
+        let session_id = drv.try_register_login(user_name, "password")
+            .await
+            .unwrap()
+            .expect("The user name is taken");
+        let user = drv.get_session_user(session_id).await.unwrap().unwrap();
+        (user.user_id, session_id)
+    }
+
+    async fn join(drv: &DbDriver, owner: UserId, group_id: UsergroupId, user_id: UserId, role: Role) {
+        let invite = InviteData { expires_at: None, max_uses: Some(1), role };
+        let code = drv.create_invite(owner, group_id, invite).await.unwrap();
+        drv.redeem_invite(user_id, code).await.unwrap();
+    }
+
+    /// An open poll in a group of an owner, a member and an observer,
+    /// after the rows left by an earlier failed run are removed
+    async fn fixture(test_name: &str) -> Fixture {
+        cleanup(test_name).await;
+
+        let drv = DbDriver::new().await;
+        let names = user_names(test_name);
+
+        let (owner, _) = register(&drv, &names[0]).await;
+        let member = register(&drv, &names[1]).await;
+        let observer = register(&drv, &names[2]).await;
+        let outsider = register(&drv, &names[3]).await;
+
+        let group = UsergroupData {
+            title: String::from("Roles test"),
+            description: String::new(),
+            parent: None,
+            include_subgroups: false,
+            tags: Vec::new(),
+            public: false,
+            deleted_at: None,
+        };
+        let group_id = drv.create_usergroup(owner, group).await.unwrap();
+        join(&drv, owner, group_id, member.0, Role::Member).await;
+        join(&drv, owner, group_id, observer.0, Role::Observer).await;
+
+        let poll = PollData {
+            question: String::from("Which?"),
+            options: vec![String::from("One"), String::from("Two")],
+            option_bodies: Vec::new(),
+            kind: PollKind::SingleChoice,
+            counting_method: CountingMethod::InstantRunoff,
+            max_score: None,
+            seats: None,
+            secret: false,
+            hide_results: false,
+            quorum: Default::default(),
+            threshold: Default::default(),
+            opens_at: None,
+            closes_at: None,
+        };
+        let poll_id = drv.create_poll(owner, group_id, poll).await.unwrap();
+        drv.set_poll_state(owner, poll_id, PollState::Open).await.unwrap();
+
+        Fixture { drv, group_id, poll_id, member, observer, outsider }
+    }
+
+    /// The CSRF check is left out, so that a 403 can only come from the roles
+    async fn call(drv: &DbDriver, req: test::TestRequest, session_id: uuid::Uuid) -> StatusCode {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(drv.clone()))
+                .service(
+                    web::scope("/api")
+                        .wrap(session::SessionMiddlewareFactory::new(drv.clone()))
+                        .configure(api_services)
+                )
+        ).await;
+
+        let req = req
+            .cookie(cookie::Cookie::new(session::SESSION_ID, session_id.to_string()))
+            .to_request();
+        test::call_service(&app, req).await.status()
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs the pleb database"]
+    async fn observer_cannot_vote() {
+        let f = fixture("vote").await;
+        let req = test::TestRequest::post()
+            .uri(&format!("/api/polls/{}/vote", f.poll_id.value))
+            .set_json(Ballot::Single(0));
+
+        let status = call(&f.drv, req, f.observer.1).await;
+        cleanup("vote").await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs the pleb database"]
+    async fn member_cannot_set_roles() {
+        let f = fixture("role").await;
+        let req = test::TestRequest::post()
+            .uri(&format!("/api/groups/{}/members/{}/role", f.group_id.value, f.observer.0.value))
+            .set_json(Role::Member);
+
+        let status = call(&f.drv, req, f.member.1).await;
+        cleanup("role").await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs the pleb database"]
+    async fn outsider_cannot_list_polls() {
+        let f = fixture("polls").await;
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/groups/{}/polls", f.group_id.value));
+
+        let status = call(&f.drv, req, f.outsider.1).await;
+        cleanup("polls").await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+}
@@ -0,0 +1,139 @@
+//! Argon2id password hashes, stored in the PHC string format.
+//!
+//! The cost parameters come from the environment: `PLEB_ARGON2_MEMORY` (KiB),
+//! `PLEB_ARGON2_ITERATIONS` and `PLEB_ARGON2_PARALLELISM`, each defaulting to
+//! the `argon2` crate's recommendation. The users registered before the hashing
+//! have their password stored in plain text, it gets hashed on their next
+//! successful login, as does a hash made with other parameters.
+
+use argon2::{Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version};
+use argon2::password_hash::SaltString;
+use rand_core::OsRng;
+
+//-------------------------------------------------------------
+
+#[derive(Clone)]
+pub struct Passwords {
+    argon2: Argon2<'static>,
+    /// Verified against when the user does not exist, so that it takes as long
+    dummy_hash: String,
+}
+
+pub enum Verified {
+    No,
+    Yes,
+    /// Matches, but the stored password is in plain text or hashed with other parameters
+    NeedsRehash,
+}
+
+impl Passwords {
+    /// Panics on invalid parameters
+    pub fn from_env() -> Self {
+        let param = |name: &str, default: u32| match std::env::var(name) {
+            Ok(value) => value.parse().unwrap_or_else(|_| panic!("{} is not a number", name)),
+            Err(_) => default,
+        };
+
+        let params = Params::new(
+            param("PLEB_ARGON2_MEMORY", Params::DEFAULT_M_COST),
+            param("PLEB_ARGON2_ITERATIONS", Params::DEFAULT_T_COST),
+            param("PLEB_ARGON2_PARALLELISM", Params::DEFAULT_P_COST),
+            None,
+        )
+        .expect("Invalid Argon2 parameters");
+
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+        let salt = SaltString::generate(&mut OsRng);
+        let dummy_hash = argon2
+            .hash_password(b"", &salt)
+            .expect("Invalid Argon2 parameters")
+            .to_string();
+
+        Self { argon2, dummy_hash }
+    }
+
+    pub fn dummy_hash(&self) -> &str {
+        &self.dummy_hash
+    }
+
+    pub fn hash(&self, password: &str) -> String {
+        let salt = SaltString::generate(&mut OsRng);
+        self.argon2
+            .hash_password(password.as_bytes(), &salt)
+            .expect("Argon2 parameters are checked by from_env")
+            .to_string()
+    }
+
+    pub fn verify(&self, password: &str, stored: &str) -> Verified {
+        // anything but a valid hash is a plain text password, even if it starts like one
+        let hash = match PasswordHash::new(stored) {
+            Ok(hash) if hash.algorithm.as_str().starts_with("argon2") => hash,
+            _ => {
+                return match constant_time_eq(password.as_bytes(), stored.as_bytes()) {
+                    true => Verified::NeedsRehash,
+                    false => Verified::No,
+                };
+            }
+        };
+
+        if self.argon2.verify_password(password.as_bytes(), &hash).is_err() {
+            return Verified::No;
+        }
+
+        let params = self.argon2.params();
+        let current = hash.algorithm == Algorithm::Argon2id.ident()
+            && Params::try_from(&hash).is_ok_and(|p| {
+                p.m_cost() == params.m_cost() && p.t_cost() == params.t_cost() && p.p_cost() == params.p_cost()
+            });
+
+        match current {
+            true => Verified::Yes,
+            false => Verified::NeedsRehash,
+        }
+    }
+}
+
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn passwords(t_cost: u32) -> Passwords {
+        let params = Params::new(8, t_cost, 1, None).unwrap();
+        Passwords { argon2: Argon2::new(Algorithm::Argon2id, Version::V0x13, params), dummy_hash: String::new() }
+    }
+
+    #[test]
+    fn plain_text_is_rehashed() {
+        assert!(matches!(passwords(1).verify("secret", "secret"), Verified::NeedsRehash));
+        assert!(matches!(passwords(1).verify("secrets", "secret"), Verified::No));
+    }
+
+    #[test]
+    fn wrong_password() {
+        let passwords = passwords(1);
+        let stored = passwords.hash("secret");
+
+        assert!(matches!(passwords.verify("secret", &stored), Verified::Yes));
+        assert!(matches!(passwords.verify("Secret", &stored), Verified::No));
+    }
+
+    #[test]
+    fn other_parameters_are_rehashed() {
+        let stored = passwords(1).hash("secret");
+
+        assert!(matches!(passwords(2).verify("secret", &stored), Verified::NeedsRehash));
+        assert!(matches!(passwords(2).verify("Secret", &stored), Verified::No));
+    }
+
+    #[test]
+    fn plain_text_looking_like_a_hash() {
+        let stored = "$argon2id$my old password";
+
+        assert!(matches!(passwords(1).verify(stored, stored), Verified::NeedsRehash));
+        assert!(matches!(passwords(1).verify("$argon2id$", stored), Verified::No));
+    }
+}
@@ -0,0 +1,41 @@
+//! The client address, by which the failed logins are counted.
+//!
+//! Behind a reverse proxy every request comes from the proxy. Its address is
+//! set in `PLEB_TRUSTED_PROXY`, and the requests it passes on are counted by
+//! the client address in its `Forwarded` or `X-Forwarded-For` header, which the
+//! proxy must set rather than append to. Unset, the headers are ignored, as
+//! anyone can send them.
+
+use std::net::{IpAddr, SocketAddr};
+
+use actix_web::HttpRequest;
+
+//-------------------------------------------------------------
+
+#[derive(Clone, Copy)]
+pub struct TrustedProxy(Option<IpAddr>);
+
+impl TrustedProxy {
+    /// Panics on an invalid address
+    pub fn from_env() -> Self {
+        match std::env::var("PLEB_TRUSTED_PROXY") {
+            Ok(value) => Self(Some(value.parse().expect("PLEB_TRUSTED_PROXY is not an IP address"))),
+            Err(_) => Self(None),
+        }
+    }
+
+    /// The peer, unless it is the trusted proxy telling a valid client address
+    pub fn client_address(&self, req: &HttpRequest) -> String {
+        let peer = req.peer_addr().map(|addr| addr.ip());
+
+        let forwarded = match (self.0, peer) {
+            (Some(proxy), Some(peer)) if proxy == peer => req
+                .connection_info()
+                .realip_remote_addr()
+                .and_then(|addr| addr.parse::<IpAddr>().ok().or_else(|| addr.parse::<SocketAddr>().ok().map(|a| a.ip()))),
+            _ => None,
+        };
+
+        forwarded.or(peer).map(|ip| ip.to_string()).unwrap_or_default()
+    }
+}
@@ -1,5 +1,6 @@
 use std::future::{ Ready, ready };
 use std::rc::Rc;
+use std::time::Duration;
 
 use actix_web::{ FromRequest, HttpRequest, HttpMessage };
 use actix_web::dev::{ self, ServiceRequest, Service };
@@ -14,6 +15,42 @@ use crate::db_driver::{ DbDriver, User, DbResult };
 pub const SESSION_ID: &'static str = "session_id";
 
 
+//-------------------------------------------------------------
+
+/// How long the sessions last, from `PLEB_SESSION_IDLE_MINUTES` (default 30)
+/// and `PLEB_SESSION_MAX_HOURS` (default 12)
+#[derive(Clone, Copy)]
+pub struct SessionTimeouts {
+    /// The session expires when unused for this long
+    pub idle: Duration,
+    /// ... and this long after the login at latest
+    pub max_age: Duration,
+}
+
+impl SessionTimeouts {
+    /// Panics on invalid values, and on the ones not fitting the i32 seconds
+    /// the pgsql functions take
+    pub fn from_env() -> Self {
+        let param = |name: &str, default: u64, unit_secs: u64| {
+            let value = match std::env::var(name) {
+                Ok(value) => value.parse().unwrap_or_else(|_| panic!("{} is not a number", name)),
+                Err(_) => default,
+            };
+            value
+                .checked_mul(unit_secs)
+                .filter(|&secs| i32::try_from(secs).is_ok())
+                .map(Duration::from_secs)
+                .unwrap_or_else(|| panic!("{} is too large", name))
+        };
+
+        Self {
+            idle: param("PLEB_SESSION_IDLE_MINUTES", 30, 60),
+            max_age: param("PLEB_SESSION_MAX_HOURS", 12, 3600),
+        }
+    }
+}
+
+
 //-------------------------------------------------------------
 
 #[derive(Clone)]
@@ -48,11 +85,18 @@ pub struct SessionMiddleware<S> {
     drv: DbDriver,
 }
 
+/// Also renews the session, the user being active
 pub async fn get_logged_in_user(req: &HttpRequest, drv: &DbDriver) -> DbResult<Option<User>> {
-    match req.cookie(SESSION_ID).and_then(|sid| uuid::Uuid::parse_str(sid.value()).ok()) {
-        None => Ok(None),
-        Some(sid) => drv.get_session_user(sid).await
+    let user = match req.cookie(SESSION_ID).and_then(|sid| uuid::Uuid::parse_str(sid.value()).ok()) {
+        None => None,
+        Some(sid) => drv.get_session_user(sid).await?
+    };
+
+    if let Some(user) = &user {
+        drv.renew_session(user.session_id).await?;
     }
+
+    Ok(user)
 }
 
 impl<S> Service<ServiceRequest> for SessionMiddleware<S>
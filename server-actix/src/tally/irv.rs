@@ -0,0 +1,179 @@
+//! Instant-runoff voting.
+//!
+//! Every round each ballot counts for its most preferred continuing option.
+//! An option holding a majority of the non-exhausted ballots wins, otherwise
+//! the option with the fewest votes is eliminated. A tie for the last place
+//! is broken by looking back at the earlier rounds (the option which had
+//! fewer votes most recently goes); options still tied are eliminated together.
+//! If all the continuing options are tied, they are all reported as winners.
+
+use plebiscite_types::OptionIdx;
+use plebiscite_types::tally::{IrvRound, IrvTally};
+
+use crate::db_driver::PollBallot;
+
+use super::option_index;
+
+//-------------------------------------------------------------
+
+pub fn count(option_count: usize, ballots: &[PollBallot]) -> IrvTally {
+    let mut continuing = vec![true; option_count];
+    let mut rounds: Vec<IrvRound> = Vec::new();
+
+    loop {
+        let mut counts = vec![0u64; option_count];
+        let mut exhausted = 0;
+
+        for ballot in ballots {
+            let weight = ballot.weight as u64;
+            match top_choice(&ballot.choices, &continuing) {
+                Some(idx) => counts[idx] += weight,
+                None => exhausted += weight,
+            }
+        }
+
+        let active: Vec<usize> = (0..option_count).filter(|&idx| continuing[idx]).collect();
+        let total: u64 = active.iter().map(|&idx| counts[idx]).sum();
+
+        let mut round = IrvRound {
+            counts: (0..option_count).map(|idx| continuing[idx].then_some(counts[idx])).collect(),
+            exhausted,
+            eliminated: Vec::new(),
+        };
+
+        if total == 0 {
+            rounds.push(round);
+            return IrvTally { rounds, winners: Vec::new() };
+        }
+
+        if let Some(&winner) = active.iter().find(|&&idx| counts[idx] * 2 > total) {
+            rounds.push(round);
+            return IrvTally { rounds, winners: vec![winner as OptionIdx] };
+        }
+
+        let min = active.iter().map(|&idx| counts[idx]).min().unwrap_or(0);
+        let mut lowest: Vec<usize> = active.iter().copied().filter(|&idx| counts[idx] == min).collect();
+
+        if lowest.len() == active.len() {
+            rounds.push(round);
+            let winners = active.into_iter().map(|idx| idx as OptionIdx).collect();
+            return IrvTally { rounds, winners };
+        }
+
+        for prev in rounds.iter().rev() {
+            if lowest.len() == 1 {
+                break;
+            }
+
+            let prev_min = lowest.iter().filter_map(|&idx| prev.counts[idx]).min();
+            lowest.retain(|&idx| prev.counts[idx] == prev_min);
+        }
+
+        for &idx in &lowest {
+            continuing[idx] = false;
+        }
+
+        round.eliminated = lowest.into_iter().map(|idx| idx as OptionIdx).collect();
+        rounds.push(round);
+    }
+}
+
+fn top_choice(ballot: &[OptionIdx], continuing: &[bool]) -> Option<usize> {
+    ballot
+        .iter()
+        .filter_map(|&opt| option_index(opt, continuing.len()))
+        .find(|&idx| continuing[idx])
+}
+
+//-------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `n` single member ballots of each ranking
+    fn ballots(groups: &[(usize, &[OptionIdx])]) -> Vec<PollBallot> {
+        groups
+            .iter()
+            .flat_map(|&(n, choices)| {
+                std::iter::repeat_with(|| PollBallot { choices: choices.to_vec(), voters: 1, weight: 1 }).take(n)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn majority_in_first_round() {
+        let tally = count(3, &ballots(&[(3, &[0, 1]), (1, &[1]), (1, &[2, 1])]));
+
+        assert_eq!(tally.winners, vec![0]);
+        assert_eq!(tally.rounds.len(), 1);
+        assert_eq!(tally.rounds[0].counts, vec![Some(3), Some(1), Some(1)]);
+        assert!(tally.rounds[0].eliminated.is_empty());
+    }
+
+    #[test]
+    fn multi_round_elimination() {
+        let tally = count(4, &ballots(&[(8, &[0]), (6, &[1, 2]), (4, &[2]), (3, &[3, 2])]));
+
+        assert_eq!(tally.rounds.len(), 3);
+        assert_eq!(tally.rounds[0].counts, vec![Some(8), Some(6), Some(4), Some(3)]);
+        assert_eq!(tally.rounds[0].eliminated, vec![3]);
+        assert_eq!(tally.rounds[1].counts, vec![Some(8), Some(6), Some(7), None]);
+        assert_eq!(tally.rounds[1].eliminated, vec![1]);
+        assert_eq!(tally.rounds[2].counts, vec![Some(8), None, Some(13), None]);
+        assert_eq!(tally.winners, vec![2]);
+    }
+
+    #[test]
+    fn last_place_tie_broken_by_earlier_round() {
+        // B and C tie in the second round, C had fewer votes in the first one
+        let tally = count(4, &ballots(&[(6, &[0]), (4, &[1, 0]), (3, &[2, 1]), (1, &[3, 2])]));
+
+        assert_eq!(tally.rounds[0].eliminated, vec![3]);
+        assert_eq!(tally.rounds[1].counts, vec![Some(6), Some(4), Some(4), None]);
+        assert_eq!(tally.rounds[1].eliminated, vec![2]);
+        assert_eq!(tally.rounds[2].counts, vec![Some(6), Some(7), None, None]);
+        assert_eq!(tally.winners, vec![1]);
+    }
+
+    #[test]
+    fn unbreakable_tie_eliminated_together() {
+        // C and D tie in the first round, there is nothing to look back at
+        let tally = count(4, &ballots(&[(3, &[0]), (2, &[1]), (1, &[2, 1]), (1, &[3, 1])]));
+
+        assert_eq!(tally.rounds[0].eliminated, vec![2, 3]);
+        assert_eq!(tally.rounds[1].counts, vec![Some(3), Some(4), None, None]);
+        assert_eq!(tally.winners, vec![1]);
+    }
+
+    #[test]
+    fn all_tied_final_round() {
+        let tally = count(3, &ballots(&[(2, &[0]), (2, &[1]), (1, &[2])]));
+
+        assert_eq!(tally.rounds.len(), 2);
+        assert_eq!(tally.rounds[0].eliminated, vec![2]);
+        assert_eq!(tally.rounds[1].counts, vec![Some(2), Some(2), None]);
+        assert!(tally.rounds[1].eliminated.is_empty());
+        assert_eq!(tally.winners, vec![0, 1]);
+    }
+
+    #[test]
+    fn exhausted_ballots_leave_the_majority() {
+        // 4 of the 7 ballots still counting is a majority, though not of all the 9
+        let tally = count(3, &ballots(&[(4, &[0]), (3, &[1]), (2, &[2])]));
+
+        assert_eq!(tally.rounds[0].exhausted, 0);
+        assert_eq!(tally.rounds[1].exhausted, 2);
+        assert_eq!(tally.rounds[1].counts, vec![Some(4), Some(3), None]);
+        assert_eq!(tally.winners, vec![0]);
+    }
+
+    #[test]
+    fn zero_ballots() {
+        let tally = count(3, &[]);
+
+        assert_eq!(tally.rounds.len(), 1);
+        assert_eq!(tally.rounds[0].counts, vec![Some(0), Some(0), Some(0)]);
+        assert!(tally.winners.is_empty());
+    }
+}
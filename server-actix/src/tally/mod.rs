@@ -0,0 +1,79 @@
+use plebiscite_types::{CountingMethod, OptionIdx, PollKind};
+use plebiscite_types::tally::{PollResults, Tally};
+
+use crate::db_driver::{PollBallot, PollTallyInfo};
+
+mod irv;
+mod outcome;
+mod plurality;
+mod schulze;
+mod stv;
+mod totals;
+
+//-------------------------------------------------------------
+
+/// Counts the stored ballots (chosen options, see `Ballot::choices`)
+/// by the rule the poll kind prescribes. Ranked ballots are counted
+/// with the given method. Every ballot counts as many votes as it carries,
+/// the raw tally counts each of them once.
+pub fn count(poll: &PollTallyInfo, method: CountingMethod, ballots: &[PollBallot]) -> PollResults {
+    let tally = count_tally(poll, method, ballots);
+
+    let single_votes: Vec<PollBallot> = ballots
+        .iter()
+        .map(|ballot| PollBallot { weight: 1, ..ballot.clone() })
+        .collect();
+    let raw_tally = count_tally(poll, method, &single_votes);
+
+    let voters = ballots.iter().map(|ballot| ballot.voters as u32).sum();
+    let votes = total_weight(ballots);
+    let eligible_voters = poll.eligible_voters as u32;
+    let turnout = if eligible_voters == 0 { 0.0 } else { voters as f64 / eligible_voters as f64 };
+
+    let outcome = outcome::evaluate(
+        &tally,
+        voters,
+        votes,
+        eligible_voters,
+        poll.max_score,
+        &poll.quorum,
+        &poll.threshold,
+    );
+
+    PollResults {
+        ballots: ballots.len() as u32,
+        voters,
+        votes,
+        eligible_voters,
+        turnout,
+        winners: tally.winners().to_vec(),
+        outcome,
+        tally,
+        raw_tally,
+    }
+}
+
+fn count_tally(poll: &PollTallyInfo, method: CountingMethod, ballots: &[PollBallot]) -> Tally {
+    let option_count = poll.option_count as usize;
+
+    match (poll.kind, method) {
+        (PollKind::SingleChoice, _) => Tally::Plurality(plurality::count(option_count, ballots)),
+        (PollKind::Ranked, CountingMethod::InstantRunoff) => Tally::InstantRunoff(irv::count(option_count, ballots)),
+        (PollKind::Ranked, CountingMethod::Schulze) => Tally::Schulze(schulze::count(option_count, ballots)),
+        (PollKind::Ranked, CountingMethod::Stv) => {
+            let seats = poll.seats.unwrap_or(1) as usize;
+            Tally::Stv(stv::count(option_count, seats, ballots))
+        },
+        (PollKind::Approval, _) => Tally::Approval(totals::count_approval(option_count, ballots)),
+        (PollKind::Score, _) => Tally::Score(totals::count_score(option_count, ballots)),
+    }
+}
+
+fn total_weight(ballots: &[PollBallot]) -> u64 {
+    ballots.iter().map(|ballot| ballot.weight as u64).sum()
+}
+
+/// Converts a stored option index, skipping the ones out of range
+fn option_index(option: OptionIdx, option_count: usize) -> Option<usize> {
+    usize::try_from(option).ok().filter(|&idx| idx < option_count)
+}
@@ -0,0 +1,154 @@
+use plebiscite_types::{Quorum, Threshold};
+use plebiscite_types::tally::{Outcome, Tally};
+
+//-------------------------------------------------------------
+
+/// The quorum counts the members taking part (through a delegate too),
+/// the threshold applies to the weighted votes.
+pub fn evaluate(
+    tally: &Tally,
+    voters: u32,
+    votes: u64,
+    eligible_voters: u32,
+    max_score: Option<i16>,
+    quorum: &Quorum,
+    threshold: &Threshold,
+) -> Outcome {
+    let min_voters_met = quorum.min_voters.is_none_or(|min| voters as i64 >= min as i64);
+    let min_percent_met = quorum
+        .min_percent
+        .is_none_or(|pct| voters as i64 * 100 >= pct as i64 * eligible_voters as i64);
+
+    if !(min_voters_met && min_percent_met) {
+        return Outcome::QuorumNotMet;
+    }
+
+    match winner_support(tally, votes, max_score) {
+        Some((support, total)) if support * threshold.denominator as f64 >= threshold.numerator as f64 * total => {
+            Outcome::Passed
+        },
+        _ => Outcome::Failed,
+    }
+}
+
+/// Share of the votes backing the single winner, as (votes, out of)
+fn winner_support(tally: &Tally, votes: u64, max_score: Option<i16>) -> Option<(f64, f64)> {
+    let single = |winners: &[i16]| match winners {
+        &[winner] => Some(winner as usize),
+        _ => None,
+    };
+
+    match tally {
+        Tally::Plurality(t) => {
+            let w = single(&t.winners)?;
+            Some((t.counts[w] as f64, votes as f64))
+        },
+        // the final round: the winner against the ballots which are not exhausted
+        Tally::InstantRunoff(t) => {
+            let w = single(&t.winners)?;
+            let last = t.rounds.last()?;
+            let total: u64 = last.counts.iter().flatten().sum();
+            Some((last.counts[w].unwrap_or(0) as f64, total as f64))
+        },
+        // the weakest of the winner's head-to-head majorities
+        Tally::Schulze(t) => {
+            let w = single(&t.winners)?;
+            (0..t.preferences.len())
+                .filter(|&other| other != w)
+                .map(|other| {
+                    let pro = t.preferences[w][other] as f64;
+                    (pro, pro + t.preferences[other][w] as f64)
+                })
+                .min_by(|a, b| (a.0 * b.1).total_cmp(&(b.0 * a.1)))
+        },
+        // an election passes once all the seats are filled, the threshold does not apply
+        Tally::Stv(t) => (t.winners.len() == t.seats as usize).then_some((1.0, 1.0)),
+        Tally::Approval(t) => {
+            let w = single(&t.winners)?;
+            Some((t.totals[w] as f64, votes as f64))
+        },
+        // the winner's score against the highest possible one
+        Tally::Score(t) => {
+            let w = single(&t.winners)?;
+            Some((t.totals[w] as f64, votes as f64 * max_score.unwrap_or(1) as f64))
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use plebiscite_types::tally::{IrvRound, IrvTally, PluralityTally, SchulzeTally};
+
+    fn plurality(counts: &[u64], winners: &[i16]) -> Tally {
+        Tally::Plurality(PluralityTally { counts: counts.to_vec(), winners: winners.to_vec() })
+    }
+
+    fn threshold(numerator: i16, denominator: i16) -> Threshold {
+        Threshold { numerator, denominator }
+    }
+
+    #[test]
+    fn percent_quorum_at_the_boundary() {
+        let tally = plurality(&[3, 1], &[0]);
+        let quorum = Quorum { min_voters: None, min_percent: Some(40) };
+
+        assert_eq!(evaluate(&tally, 4, 4, 10, None, &quorum, &Threshold::default()), Outcome::Passed);
+        assert_eq!(evaluate(&tally, 3, 4, 10, None, &quorum, &Threshold::default()), Outcome::QuorumNotMet);
+    }
+
+    #[test]
+    fn two_thirds_threshold() {
+        let quorum = Quorum::default();
+
+        let tally = plurality(&[2, 1], &[0]);
+        assert_eq!(evaluate(&tally, 3, 3, 3, None, &quorum, &threshold(2, 3)), Outcome::Passed);
+
+        let tally = plurality(&[3, 2], &[0]);
+        assert_eq!(evaluate(&tally, 5, 5, 5, None, &quorum, &threshold(2, 3)), Outcome::Failed);
+    }
+
+    #[test]
+    fn tied_winners_fail() {
+        let tally = plurality(&[2, 2], &[0, 1]);
+        assert_eq!(evaluate(&tally, 4, 4, 4, None, &Quorum::default(), &Threshold::default()), Outcome::Failed);
+    }
+
+    /// The exhausted ballots are left out of the final round's total
+    #[test]
+    fn irv_final_round_support() {
+        let tally = Tally::InstantRunoff(IrvTally {
+            rounds: vec![
+                IrvRound { counts: vec![Some(5), Some(3), Some(4)], exhausted: 3, eliminated: vec![1] },
+                IrvRound { counts: vec![Some(6), None, Some(4)], exhausted: 5, eliminated: vec![2] },
+            ],
+            winners: vec![0],
+        });
+
+        assert_eq!(winner_support(&tally, 15, None), Some((6.0, 10.0)));
+        assert_eq!(evaluate(&tally, 15, 15, 15, None, &Quorum::default(), &threshold(3, 5)), Outcome::Passed);
+        assert_eq!(evaluate(&tally, 15, 15, 15, None, &Quorum::default(), &threshold(2, 3)), Outcome::Failed);
+    }
+
+    /// The winner's support is its weakest head-to-head majority
+    #[test]
+    fn schulze_weakest_pair_support() {
+        let tally = Tally::Schulze(SchulzeTally {
+            preferences: vec![
+                vec![0, 7, 6],
+                vec![3, 0, 5],
+                vec![4, 5, 0],
+            ],
+            strongest_paths: vec![
+                vec![0, 7, 6],
+                vec![0, 0, 0],
+                vec![0, 0, 0],
+            ],
+            winners: vec![0],
+        });
+
+        assert_eq!(winner_support(&tally, 10, None), Some((6.0, 10.0)));
+        assert_eq!(evaluate(&tally, 10, 10, 10, None, &Quorum::default(), &threshold(3, 5)), Outcome::Passed);
+        assert_eq!(evaluate(&tally, 10, 10, 10, None, &Quorum::default(), &threshold(2, 3)), Outcome::Failed);
+    }
+}
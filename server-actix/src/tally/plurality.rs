@@ -0,0 +1,30 @@
+use plebiscite_types::OptionIdx;
+use plebiscite_types::tally::PluralityTally;
+
+use crate::db_driver::PollBallot;
+
+use super::option_index;
+
+//-------------------------------------------------------------
+
+pub fn count(option_count: usize, ballots: &[PollBallot]) -> PluralityTally {
+    let mut counts = vec![0u64; option_count];
+
+    for ballot in ballots {
+        if let Some(idx) = ballot.choices.first().and_then(|&opt| option_index(opt, option_count)) {
+            counts[idx] += ballot.weight as u64;
+        }
+    }
+
+    let max = counts.iter().copied().max().unwrap_or(0);
+    let winners = if max == 0 {
+        Vec::new()
+    } else {
+        (0..option_count)
+            .filter(|&idx| counts[idx] == max)
+            .map(|idx| idx as OptionIdx)
+            .collect()
+    };
+
+    PluralityTally { counts, winners }
+}
@@ -0,0 +1,140 @@
+//! Schulze method.
+//!
+//! Options a ballot leaves out are ranked equally, below all the listed ones.
+
+use plebiscite_types::OptionIdx;
+use plebiscite_types::tally::SchulzeTally;
+
+use crate::db_driver::PollBallot;
+
+use super::option_index;
+
+//-------------------------------------------------------------
+
+pub fn count(option_count: usize, ballots: &[PollBallot]) -> SchulzeTally {
+    let n = option_count;
+    let mut preferences = vec![vec![0u64; n]; n];
+
+    for ballot in ballots {
+        // rank of each option on this ballot, unlisted ones share the last place
+        let mut rank = vec![usize::MAX; n];
+        for (pos, idx) in ballot.choices.iter().filter_map(|&opt| option_index(opt, n)).enumerate() {
+            rank[idx] = rank[idx].min(pos);
+        }
+
+        for a in 0..n {
+            for b in 0..n {
+                if rank[a] < rank[b] {
+                    preferences[a][b] += ballot.weight as u64;
+                }
+            }
+        }
+    }
+
+    let mut strongest_paths = vec![vec![0u64; n]; n];
+    for a in 0..n {
+        for b in 0..n {
+            if a != b && preferences[a][b] > preferences[b][a] {
+                strongest_paths[a][b] = preferences[a][b];
+            }
+        }
+    }
+
+    for k in 0..n {
+        for a in (0..n).filter(|&a| a != k) {
+            for b in (0..n).filter(|&b| b != k && b != a) {
+                let via_k = strongest_paths[a][k].min(strongest_paths[k][b]);
+                if via_k > strongest_paths[a][b] {
+                    strongest_paths[a][b] = via_k;
+                }
+            }
+        }
+    }
+
+    let winners = if ballots.is_empty() {
+        Vec::new()
+    } else {
+        (0..n)
+            .filter(|&a| (0..n).all(|b| strongest_paths[a][b] >= strongest_paths[b][a]))
+            .map(|a| a as OptionIdx)
+            .collect()
+    };
+
+    SchulzeTally {
+        preferences,
+        strongest_paths,
+        winners,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `n` single member ballots of each ranking
+    fn ballots(groups: &[(usize, &[OptionIdx])]) -> Vec<PollBallot> {
+        groups
+            .iter()
+            .flat_map(|&(n, choices)| {
+                std::iter::repeat_with(|| PollBallot { choices: choices.to_vec(), voters: 1, weight: 1 }).take(n)
+            })
+            .collect()
+    }
+
+    /// The example of the Wikipedia article "Schulze method": 45 voters rank A to E
+    #[test]
+    fn wikipedia_example() {
+        const A: OptionIdx = 0;
+        const B: OptionIdx = 1;
+        const C: OptionIdx = 2;
+        const D: OptionIdx = 3;
+        const E: OptionIdx = 4;
+
+        let tally = count(5, &ballots(&[
+            (5, &[A, C, B, E, D]),
+            (5, &[A, D, E, C, B]),
+            (8, &[B, E, D, A, C]),
+            (3, &[C, A, B, E, D]),
+            (7, &[C, A, E, B, D]),
+            (2, &[C, B, A, D, E]),
+            (7, &[D, C, E, B, A]),
+            (8, &[E, B, A, D, C]),
+        ]));
+
+        assert_eq!(tally.preferences, vec![
+            vec![0, 20, 26, 30, 22],
+            vec![25, 0, 16, 33, 18],
+            vec![19, 29, 0, 17, 24],
+            vec![15, 12, 28, 0, 14],
+            vec![23, 27, 21, 31, 0],
+        ]);
+        assert_eq!(tally.strongest_paths, vec![
+            vec![0, 28, 28, 30, 24],
+            vec![25, 0, 28, 33, 24],
+            vec![25, 29, 0, 29, 24],
+            vec![25, 28, 28, 0, 24],
+            vec![25, 28, 28, 31, 0],
+        ]);
+        assert_eq!(tally.winners, vec![E]);
+    }
+
+    #[test]
+    fn unlisted_options_share_the_last_place() {
+        let tally = count(3, &ballots(&[(2, &[2]), (1, &[0, 1])]));
+
+        assert_eq!(tally.preferences, vec![
+            vec![0, 1, 1],
+            vec![0, 0, 1],
+            vec![2, 2, 0],
+        ]);
+        assert_eq!(tally.winners, vec![2]);
+    }
+
+    #[test]
+    fn zero_ballots() {
+        let tally = count(3, &[]);
+
+        assert_eq!(tally.preferences, vec![vec![0; 3]; 3]);
+        assert!(tally.winners.is_empty());
+    }
+}
@@ -0,0 +1,256 @@
+//! Single transferable vote.
+//!
+//! The quota is the Droop one, `floor(votes / (seats + 1)) + 1`. Every round
+//! each ballot counts its current value for its most preferred continuing
+//! option. The options reaching the quota are elected and their surplus is
+//! transferred by the weighted inclusive Gregory method: all the ballots
+//! counting for an elected option move on to their next preferences,
+//! with their value multiplied by `surplus / votes`. If no option reaches the
+//! quota, the one with the fewest votes is eliminated and its ballots move on
+//! at their full value, ties for the last place are broken as in `irv`.
+//! Once there are no more continuing options than free seats, they are all elected.
+
+use plebiscite_types::OptionIdx;
+use plebiscite_types::tally::{StvRound, StvTally, StvTransfer};
+
+use crate::db_driver::PollBallot;
+
+use super::{option_index, total_weight};
+
+//-------------------------------------------------------------
+
+pub fn count(option_count: usize, seats: usize, ballots: &[PollBallot]) -> StvTally {
+    let quota = (total_weight(ballots) / (seats as u64 + 1) + 1) as f64;
+
+    let mut values: Vec<f64> = ballots.iter().map(|ballot| ballot.weight as f64).collect();
+    let mut continuing = vec![true; option_count];
+    let mut rounds: Vec<StvRound> = Vec::new();
+    let mut winners: Vec<OptionIdx> = Vec::new();
+
+    if ballots.is_empty() {
+        return StvTally { seats: seats as u32, quota, rounds, winners };
+    }
+
+    while winners.len() < seats {
+        let holders: Vec<Option<usize>> = ballots
+            .iter()
+            .map(|ballot| top_choice(&ballot.choices, &continuing))
+            .collect();
+
+        let mut counts = vec![0.0; option_count];
+        let mut exhausted = 0.0;
+        for (holder, &value) in holders.iter().zip(&values) {
+            match holder {
+                Some(idx) => counts[*idx] += value,
+                None => exhausted += value,
+            }
+        }
+
+        let mut round = StvRound {
+            counts: (0..option_count).map(|idx| continuing[idx].then_some(counts[idx])).collect(),
+            exhausted,
+            elected: Vec::new(),
+            eliminated: Vec::new(),
+            transfers: Vec::new(),
+        };
+
+        let mut active: Vec<usize> = (0..option_count).filter(|&idx| continuing[idx]).collect();
+        active.sort_by(|&a, &b| counts[b].total_cmp(&counts[a]));
+
+        let free_seats = seats - winners.len();
+        if active.len() <= free_seats {
+            round.elected = active.into_iter().map(|idx| idx as OptionIdx).collect();
+            winners.extend(&round.elected);
+            rounds.push(round);
+            break;
+        }
+
+        let elected: Vec<usize> = active.iter().copied().filter(|&idx| counts[idx] >= quota).collect();
+
+        let moving: Vec<(usize, f64)> = if !elected.is_empty() {
+            elected.iter().map(|&idx| (idx, (counts[idx] - quota) / counts[idx])).collect()
+        } else {
+            let lowest = lowest_options(&active, &counts, &rounds);
+
+            // a tie which cannot be broken and leaves too few options for the free seats
+            if active.len() - lowest.len() < free_seats {
+                round.elected = active.into_iter().map(|idx| idx as OptionIdx).collect();
+                winners.extend(&round.elected);
+                rounds.push(round);
+                break;
+            }
+
+            lowest.into_iter().map(|idx| (idx, 1.0)).collect()
+        };
+
+        for &(idx, _) in &moving {
+            continuing[idx] = false;
+        }
+
+        for &(from, transfer_value) in &moving {
+            let mut transfer = StvTransfer {
+                from: from as OptionIdx,
+                transfer_value,
+                to: vec![0.0; option_count],
+                exhausted: 0.0,
+            };
+
+            for (b, ballot) in ballots.iter().enumerate() {
+                if holders[b] != Some(from) {
+                    continue;
+                }
+
+                values[b] *= transfer_value;
+                match top_choice(&ballot.choices, &continuing) {
+                    Some(idx) => transfer.to[idx] += values[b],
+                    None => transfer.exhausted += values[b],
+                }
+            }
+
+            round.transfers.push(transfer);
+        }
+
+        if elected.is_empty() {
+            round.eliminated = moving.iter().map(|&(idx, _)| idx as OptionIdx).collect();
+        } else {
+            round.elected = elected.iter().map(|&idx| idx as OptionIdx).collect();
+            winners.extend(&round.elected);
+        }
+
+        rounds.push(round);
+    }
+
+    StvTally { seats: seats as u32, quota, rounds, winners }
+}
+
+/// The options with the fewest votes, a tie is broken by looking back
+/// at the earlier rounds (the option which had fewer votes most recently)
+fn lowest_options(active: &[usize], counts: &[f64], rounds: &[StvRound]) -> Vec<usize> {
+    let min = active.iter().map(|&idx| counts[idx]).fold(f64::INFINITY, f64::min);
+    let mut lowest: Vec<usize> = active.iter().copied().filter(|&idx| counts[idx] == min).collect();
+
+    for prev in rounds.iter().rev() {
+        if lowest.len() == 1 {
+            break;
+        }
+
+        let prev_min = lowest
+            .iter()
+            .filter_map(|&idx| prev.counts[idx])
+            .fold(f64::INFINITY, f64::min);
+        lowest.retain(|&idx| prev.counts[idx] == Some(prev_min));
+    }
+
+    lowest
+}
+
+fn top_choice(ballot: &[OptionIdx], continuing: &[bool]) -> Option<usize> {
+    ballot
+        .iter()
+        .filter_map(|&opt| option_index(opt, continuing.len()))
+        .find(|&idx| continuing[idx])
+}
+
+//-------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `n` single member ballots of each ranking
+    fn ballots(groups: &[(usize, &[OptionIdx])]) -> Vec<PollBallot> {
+        groups
+            .iter()
+            .flat_map(|&(n, choices)| {
+                std::iter::repeat_with(|| PollBallot { choices: choices.to_vec(), voters: 1, weight: 1 }).take(n)
+            })
+            .collect()
+    }
+
+    fn transfer(round: &StvRound) -> (OptionIdx, f64, &[f64], f64) {
+        assert_eq!(round.transfers.len(), 1);
+        let t = &round.transfers[0];
+        (t.from, t.transfer_value, &t.to, t.exhausted)
+    }
+
+    /// The example election of the Wikipedia article "Single transferable vote":
+    /// 20 voters choose 3 of Oranges, Pears, Chocolate, Strawberries and Hamburgers.
+    #[test]
+    fn wikipedia_food_election() {
+        const ORANGES: OptionIdx = 0;
+        const PEARS: OptionIdx = 1;
+        const CHOCOLATE: OptionIdx = 2;
+        const STRAWBERRIES: OptionIdx = 3;
+        const HAMBURGERS: OptionIdx = 4;
+
+        let tally = count(5, 3, &ballots(&[
+            (4, &[ORANGES]),
+            (2, &[PEARS, ORANGES]),
+            (8, &[CHOCOLATE, STRAWBERRIES]),
+            (4, &[CHOCOLATE, HAMBURGERS]),
+            (1, &[STRAWBERRIES]),
+            (1, &[HAMBURGERS]),
+        ]));
+
+        assert_eq!(tally.quota, 6.0);
+        assert_eq!(tally.rounds.len(), 5);
+
+        // Chocolate is elected, its 6 surplus votes move on at half the value
+        let round = &tally.rounds[0];
+        assert_eq!(round.counts, vec![Some(4.0), Some(2.0), Some(12.0), Some(1.0), Some(1.0)]);
+        assert_eq!(round.elected, vec![CHOCOLATE]);
+        assert_eq!(transfer(round), (CHOCOLATE, 0.5, &[0.0, 0.0, 0.0, 4.0, 2.0][..], 0.0));
+
+        // nobody reaches the quota, Pears go
+        let round = &tally.rounds[1];
+        assert_eq!(round.counts, vec![Some(4.0), Some(2.0), None, Some(5.0), Some(3.0)]);
+        assert_eq!(round.eliminated, vec![PEARS]);
+        assert_eq!(transfer(round), (PEARS, 1.0, &[2.0, 0.0, 0.0, 0.0, 0.0][..], 0.0));
+
+        // Oranges reach the quota exactly, there is no surplus
+        let round = &tally.rounds[2];
+        assert_eq!(round.counts, vec![Some(6.0), None, None, Some(5.0), Some(3.0)]);
+        assert_eq!(round.elected, vec![ORANGES]);
+        assert_eq!(transfer(round), (ORANGES, 0.0, &[0.0; 5][..], 0.0));
+
+        // Hamburgers go, their ballots rank nothing else
+        let round = &tally.rounds[3];
+        assert_eq!(round.counts, vec![None, None, None, Some(5.0), Some(3.0)]);
+        assert_eq!(round.eliminated, vec![HAMBURGERS]);
+        assert_eq!(transfer(round), (HAMBURGERS, 1.0, &[0.0; 5][..], 3.0));
+
+        // Strawberries are the last continuing option for the last seat
+        let round = &tally.rounds[4];
+        assert_eq!(round.counts, vec![None, None, None, Some(5.0), None]);
+        assert_eq!(round.exhausted, 3.0);
+        assert_eq!(round.elected, vec![STRAWBERRIES]);
+
+        assert_eq!(tally.winners, vec![CHOCOLATE, ORANGES, STRAWBERRIES]);
+    }
+
+    #[test]
+    fn tie_at_elimination() {
+        // B and C tie for the last place with nothing to look back at, both go
+        let tally = count(3, 1, &ballots(&[(4, &[0]), (2, &[1, 0]), (2, &[2, 1])]));
+
+        assert_eq!(tally.quota, 5.0);
+        assert_eq!(tally.rounds[0].eliminated, vec![1, 2]);
+        assert_eq!(tally.rounds[1].counts, vec![Some(6.0), None, None]);
+        assert_eq!(tally.rounds[1].exhausted, 2.0);
+        assert_eq!(tally.winners, vec![0]);
+    }
+
+    #[test]
+    fn unbreakable_tie_for_the_last_seat() {
+        // after A is elected, B and C tie in every round for the one free seat
+        let tally = count(3, 2, &ballots(&[(4, &[0]), (1, &[1]), (1, &[2])]));
+
+        assert_eq!(tally.quota, 3.0);
+        assert_eq!(tally.rounds[0].elected, vec![0]);
+        assert_eq!(transfer(&tally.rounds[0]), (0, 0.25, &[0.0; 3][..], 1.0));
+        assert_eq!(tally.rounds[1].counts, vec![None, Some(1.0), Some(1.0)]);
+        assert_eq!(tally.rounds[1].elected, vec![1, 2]);
+        assert!(tally.rounds[1].eliminated.is_empty());
+        assert_eq!(tally.winners, vec![0, 1, 2]);
+    }
+}
@@ -0,0 +1,53 @@
+use plebiscite_types::OptionIdx;
+use plebiscite_types::tally::TotalsTally;
+
+use crate::db_driver::PollBallot;
+
+use super::{option_index, total_weight};
+
+//-------------------------------------------------------------
+
+/// Each ballot lists the approved options
+pub fn count_approval(option_count: usize, ballots: &[PollBallot]) -> TotalsTally {
+    let mut totals = vec![0u64; option_count];
+
+    for ballot in ballots {
+        for idx in ballot.choices.iter().filter_map(|&opt| option_index(opt, option_count)) {
+            totals[idx] += ballot.weight as u64;
+        }
+    }
+
+    summarize(totals, total_weight(ballots))
+}
+
+/// Each ballot holds the scores of all the options
+pub fn count_score(option_count: usize, ballots: &[PollBallot]) -> TotalsTally {
+    let mut totals = vec![0u64; option_count];
+
+    for ballot in ballots {
+        for (total, &score) in totals.iter_mut().zip(&ballot.choices) {
+            *total += u64::try_from(score).unwrap_or(0) * ballot.weight as u64;
+        }
+    }
+
+    summarize(totals, total_weight(ballots))
+}
+
+fn summarize(totals: Vec<u64>, votes: u64) -> TotalsTally {
+    let averages = totals
+        .iter()
+        .map(|&total| if votes == 0 { 0.0 } else { total as f64 / votes as f64 })
+        .collect();
+
+    let max = totals.iter().copied().max().unwrap_or(0);
+    let winners = if votes == 0 {
+        Vec::new()
+    } else {
+        (0..totals.len())
+            .filter(|&idx| totals[idx] == max)
+            .map(|idx| idx as OptionIdx)
+            .collect()
+    };
+
+    TotalsTally { totals, averages, winners }
+}
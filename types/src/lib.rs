@@ -1,4 +1,9 @@
+#[macro_use]
+mod pg_enum;
+
 pub mod object_id;
+pub mod proposal;
+pub mod tally;
 
 use object_id::ObjectId;
 
@@ -10,7 +15,7 @@ pub struct LoginInfo {
 
 //-----------------------------------------------------------
 
-#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct UserData {
     pub user_name: String,
     pub full_name: String,
@@ -18,16 +23,260 @@ pub struct UserData {
 
 pub type UserId = ObjectId<UserData>;
 
+/// One of the user's active logins, the secret session cookie is not exposed
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SessionInfo {
+    pub created_at: Timestamp,
+    pub expires: Timestamp,
+    /// The session of the request
+    pub current: bool,
+}
+
+pub type SessionId = ObjectId<SessionInfo>;
+
+pub type Session = (SessionId, SessionInfo);
+
 //-----------------------------------------------------------
 
 #[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct UsergroupData {
     pub title: String,
-    //pub tags: Option<i64>,
+    #[serde(default)]
+    pub description: String,
+    /// None at the top of the tree
+    pub parent: Option<UsergroupId>,
+    /// The members of the subgroups take part in the group's polls too
+    pub include_subgroups: bool,
+    /// Lowercase, sorted, see `normalize_tags` in the database
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Listed in the group directory, anyone can ask to join
+    #[serde(default)]
+    pub public: bool,
+    /// Set when the group is soft deleted, only its owners see it then
+    pub deleted_at: Option<Timestamp>,
 }
 
 pub type UsergroupId = ObjectId<UsergroupData>;
 
 pub type Usergroup = (UsergroupId, UsergroupData);
 
+pg_enum! {
+    /// Member's role in a usergroup. Owners manage the admins and owners, admins manage
+    /// the members, their weights and polls. Observers cannot vote, nor create anything.
+    pub enum Role as "member_role" {
+        Owner = "owner",
+        Admin = "admin",
+        Member = "member",
+        Observer = "observer",
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct MemberInfo {
+    pub user: UserData,
+    pub role: Role,
+    pub weight: i32,
+    /// A member of a subgroup, rather than of the group itself
+    pub inherited: bool,
+}
+
+pub type Member = (UserId, MemberInfo);
+
+/// Someone asking to join a public usergroup
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct JoinRequestInfo {
+    pub user: UserData,
+    pub created_at: Timestamp,
+}
+
+pub type JoinRequest = (UserId, JoinRequestInfo);
+
+/// An invite to join a usergroup, created by its admins. It can be used until
+/// it expires, gets used `max_uses` times or is revoked.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct InviteData {
+    pub expires_at: Option<Timestamp>,
+    pub max_uses: Option<i32>,
+    /// The role the invited users join with, Member or Observer
+    pub role: Role,
+}
+
+pub type InviteId = ObjectId<InviteData>;
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct InviteInfo {
+    /// What the invited user redeems
+    pub code: String,
+    pub created_by: UserId,
+    pub created_at: Timestamp,
+    pub uses: i32,
+    pub data: InviteData,
+}
+
+pub type Invite = (InviteId, InviteInfo);
+
+//-----------------------------------------------------------
+
+/// Seconds since the unix epoch
+pub type Timestamp = i64;
+
+/// Position of an option in `PollData::options`
+pub type OptionIdx = i16;
+
+pg_enum! {
+    #[derive(Default)]
+    pub enum PollKind as "poll_kind" {
+        #[default]
+        SingleChoice = "single_choice",
+        Ranked = "ranked",
+        Approval = "approval",
+        Score = "score",
+    }
+}
+
+pg_enum! {
+    /// How the ranked ballots are counted
+    #[derive(Default)]
+    pub enum CountingMethod as "counting_method" {
+        #[default]
+        InstantRunoff = "instant_runoff",
+        Schulze = "schulze",
+        /// Single transferable vote, electing `PollData::seats` options
+        Stv = "stv",
+    }
+}
+
+pg_enum! {
+    /// A draft is editable by its author and opens either manually or at `opens_at`.
+    /// An open poll accepts votes until it is closed manually or at `closes_at`.
+    pub enum PollState as "poll_state" {
+        Draft = "draft",
+        Open = "open",
+        Closed = "closed",
+        Archived = "archived",
+    }
+}
+
+/// Minimal participation for a poll to be valid, both limits apply if set
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Quorum {
+    pub min_voters: Option<i32>,
+    /// Percentage of the group members at the time the poll opened
+    pub min_percent: Option<i16>,
+}
+
+/// Share of the votes the winner needs for the poll to pass, e.g. 2/3.
+/// The default 0/1 only requires a single winner.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Threshold {
+    pub numerator: i16,
+    pub denominator: i16,
+}
+
+impl Default for Threshold {
+    fn default() -> Self {
+        Self { numerator: 0, denominator: 1 }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PollData {
+    pub question: String,
+    pub options: Vec<String>,
+    /// Text behind every option, such as the proposal and the amendments of a
+    /// promoted poll. Either empty or one per option.
+    #[serde(default)]
+    pub option_bodies: Vec<String>,
+    #[serde(default)]
+    pub kind: PollKind,
+    #[serde(default)]
+    pub counting_method: CountingMethod,
+    /// Highest score of a `Score` poll, the lowest one is always 0
+    pub max_score: Option<i16>,
+    /// Number of options to elect by STV, one if not set
+    pub seats: Option<i16>,
+    /// Ballots are stored apart from the voters and cannot be changed once cast,
+    /// they are counted once the poll closes. Every ballot counts once, so all
+    /// the members must have the weight 1 and keep it until the poll closes.
+    #[serde(default)]
+    pub secret: bool,
+    /// Only the author and the group admins see the results before the poll closes
+    #[serde(default)]
+    pub hide_results: bool,
+    #[serde(default)]
+    pub quorum: Quorum,
+    #[serde(default)]
+    pub threshold: Threshold,
+    pub opens_at: Option<Timestamp>,
+    pub closes_at: Option<Timestamp>,
+}
+
+pub type PollId = ObjectId<PollData>;
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PollInfo {
+    pub author_id: UserId,
+    pub state: PollState,
+    pub data: PollData,
+}
+
+pub type Poll = (PollId, PollInfo);
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Ballot {
+    Single(OptionIdx),
+    /// Options in the order of preference, the most preferred first.
+    /// Options left out are ranked below all the listed ones.
+    Ranked(Vec<OptionIdx>),
+    /// Any number of approved options
+    Approval(Vec<OptionIdx>),
+    /// Score of every option, indexed by `OptionIdx`
+    Score(Vec<i16>),
+}
+
+impl Ballot {
+    pub fn kind(&self) -> PollKind {
+        match self {
+            Ballot::Single(_) => PollKind::SingleChoice,
+            Ballot::Ranked(_) => PollKind::Ranked,
+            Ballot::Approval(_) => PollKind::Approval,
+            Ballot::Score(_) => PollKind::Score,
+        }
+    }
+
+    /// Chosen options (or scores) as they are stored in the database
+    pub fn choices(&self) -> Vec<i16> {
+        match self {
+            Ballot::Single(option) => vec![*option],
+            Ballot::Ranked(options) | Ballot::Approval(options) => options.clone(),
+            Ballot::Score(scores) => scores.clone(),
+        }
+    }
+}
+
+/// A member's vote cast by another member, see `delegations` in the database.
+/// Votes in secret polls cannot be delegated.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Delegation {
+    /// The single poll the delegation applies to, all the group's polls if None
+    pub poll_id: Option<PollId>,
+    pub delegate_id: UserId,
+}
+
+//-----------------------------------------------------------
+
+/// Proof of a cast ballot. The commitment is the hex encoded SHA-256 of
+/// `"{poll_id}:{choices}:{nonce}"`, with the choices (see `Ballot::choices`)
+/// joined by commas. Once the poll is closed, the commitments of all the
+/// counted ballots are published, so the voter can check theirs is there.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct VoteReceipt {
+    pub commitment: String,
+    pub nonce: String,
+    /// The voter's previous ballot (and its receipt) got replaced
+    pub replaced: bool,
+}
+
 //-----------------------------------------------------------
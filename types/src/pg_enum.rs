@@ -0,0 +1,59 @@
+/// Declares a fieldless enum which is serialized as its label and maps
+/// onto the Postgres ENUM type with the given name and the same labels.
+macro_rules! pg_enum {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident as $pg_name:literal {
+            $( $(#[$vmeta:meta])* $variant:ident = $label:literal ),+ $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+        pub enum $name {
+            $( $(#[$vmeta])* #[serde(rename = $label)] $variant ),+
+        }
+
+        impl $name {
+            pub fn as_str(self) -> &'static str {
+                match self {
+                    $( $name::$variant => $label ),+
+                }
+            }
+        }
+
+        #[cfg(feature = "postgres")]
+        impl<'a> postgres_types::FromSql<'a> for $name {
+            fn from_sql(
+                _ty: &postgres_types::Type,
+                raw: &'a [u8],
+            ) -> Result<Self, Box<dyn std::error::Error + Sync + Send>> {
+                match std::str::from_utf8(raw)? {
+                    $( $label => Ok($name::$variant), )+
+                    other => Err(format!("Unknown {} label: '{}'", $pg_name, other).into()),
+                }
+            }
+
+            fn accepts(ty: &postgres_types::Type) -> bool {
+                ty.name() == $pg_name
+            }
+        }
+
+        #[cfg(feature = "postgres")]
+        impl postgres_types::ToSql for $name {
+            fn to_sql(
+                &self,
+                _ty: &postgres_types::Type,
+                out: &mut bytes::BytesMut,
+            ) -> Result<postgres_types::IsNull, Box<dyn std::error::Error + Sync + Send + 'static>> {
+                out.extend_from_slice(self.as_str().as_bytes());
+                Ok(postgres_types::IsNull::No)
+            }
+
+            fn accepts(ty: &postgres_types::Type) -> bool {
+                ty.name() == $pg_name
+            }
+
+            postgres_types::to_sql_checked!();
+        }
+    };
+}
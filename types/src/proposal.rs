@@ -0,0 +1,73 @@
+use crate::object_id::ObjectId;
+use crate::{PollId, Timestamp, UserId};
+
+//-----------------------------------------------------------
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ProposalData {
+    pub title: String,
+    pub body: String,
+}
+
+pub type ProposalId = ObjectId<ProposalData>;
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ProposalInfo {
+    pub author_id: UserId,
+    pub created_at: Timestamp,
+    /// The poll the proposal has been promoted to, it takes no more amendments then
+    pub poll_id: Option<PollId>,
+    pub data: ProposalData,
+}
+
+pub type Proposal = (ProposalId, ProposalInfo);
+
+//-----------------------------------------------------------
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CommentData {
+    /// The comment this one replies to, None at the top of a thread
+    pub parent_id: Option<CommentId>,
+    pub body: String,
+}
+
+pub type CommentId = ObjectId<CommentData>;
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CommentInfo {
+    pub author_id: UserId,
+    pub created_at: Timestamp,
+    pub data: CommentData,
+}
+
+pub type Comment = (CommentId, CommentInfo);
+
+//-----------------------------------------------------------
+
+pg_enum! {
+    /// Decided by the author of the proposal
+    pub enum AmendmentState as "amendment_state" {
+        Pending = "pending",
+        Accepted = "accepted",
+        Rejected = "rejected",
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct AmendmentData {
+    /// Becomes an option of the poll, once accepted
+    pub title: String,
+    pub body: String,
+}
+
+pub type AmendmentId = ObjectId<AmendmentData>;
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct AmendmentInfo {
+    pub author_id: UserId,
+    pub state: AmendmentState,
+    pub created_at: Timestamp,
+    pub data: AmendmentData,
+}
+
+pub type Amendment = (AmendmentId, AmendmentInfo);
@@ -0,0 +1,150 @@
+use crate::OptionIdx;
+
+//-----------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Passed,
+    /// No single winner, or the winner's share is below the poll's threshold
+    Failed,
+    QuorumNotMet,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PollResults {
+    /// Number of ballots cast
+    pub ballots: u32,
+    /// Members the ballots stand for, the delegating ones included
+    pub voters: u32,
+    /// Votes the ballots carry, weighted by the members' weights
+    pub votes: u64,
+    /// Group members at the time the poll opened (or now, if it has not yet)
+    pub eligible_voters: u32,
+    /// Share of the eligible voters taking part, from 0 to 1
+    pub turnout: f64,
+    /// Winners of the weighted count, see the tally for the ties
+    pub winners: Vec<OptionIdx>,
+    pub outcome: Outcome,
+    /// The weighted count, which the outcome is based on
+    pub tally: Tally,
+    /// Every ballot counted as a single vote
+    pub raw_tally: Tally,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Tally {
+    Plurality(PluralityTally),
+    InstantRunoff(IrvTally),
+    Schulze(SchulzeTally),
+    Stv(StvTally),
+    Approval(TotalsTally),
+    Score(TotalsTally),
+}
+
+impl Tally {
+    pub fn winners(&self) -> &[OptionIdx] {
+        match self {
+            Tally::Plurality(t) => &t.winners,
+            Tally::InstantRunoff(t) => &t.winners,
+            Tally::Schulze(t) => &t.winners,
+            Tally::Stv(t) => &t.winners,
+            Tally::Approval(t) | Tally::Score(t) => &t.winners,
+        }
+    }
+}
+
+//-----------------------------------------------------------
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PluralityTally {
+    /// Votes per option, indexed by `OptionIdx`
+    pub counts: Vec<u64>,
+    /// More than one winner means a tie, none - no ballots at all
+    pub winners: Vec<OptionIdx>,
+}
+
+//-----------------------------------------------------------
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TotalsTally {
+    /// Approvals or scores summed per option, indexed by `OptionIdx`
+    pub totals: Vec<u64>,
+    /// Totals divided by the number of votes
+    pub averages: Vec<f64>,
+    /// More than one winner means a tie, none - no ballots at all
+    pub winners: Vec<OptionIdx>,
+}
+
+//-----------------------------------------------------------
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct IrvRound {
+    /// First preferences among the continuing options, indexed by `OptionIdx`.
+    /// Options eliminated in earlier rounds have `None`.
+    pub counts: Vec<Option<u64>>,
+    /// Votes on the ballots which rank none of the continuing options
+    pub exhausted: u64,
+    /// Options eliminated at the end of this round
+    pub eliminated: Vec<OptionIdx>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct IrvTally {
+    pub rounds: Vec<IrvRound>,
+    /// More than one winner means an unbreakable tie, none - no ballots at all
+    pub winners: Vec<OptionIdx>,
+}
+
+//-----------------------------------------------------------
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SchulzeTally {
+    /// `preferences[a][b]` - number of votes preferring option `a` to option `b`
+    pub preferences: Vec<Vec<u64>>,
+    /// `strongest_paths[a][b]` - strength of the strongest path from `a` to `b`
+    pub strongest_paths: Vec<Vec<u64>>,
+    /// More than one winner means a tie, none - no ballots at all
+    pub winners: Vec<OptionIdx>,
+}
+
+//-----------------------------------------------------------
+
+/// Votes moved on from an elected option's surplus or from an eliminated option
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct StvTransfer {
+    pub from: OptionIdx,
+    /// Part of the value of each ballot which moves on: the surplus over
+    /// the quota divided by the option's votes, or 1 on elimination
+    pub transfer_value: f64,
+    /// Votes received by each option, indexed by `OptionIdx`
+    pub to: Vec<f64>,
+    /// Votes on the ballots which rank none of the continuing options
+    pub exhausted: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct StvRound {
+    /// Votes of the continuing options, indexed by `OptionIdx`.
+    /// Options elected or eliminated in earlier rounds have `None`.
+    pub counts: Vec<Option<f64>>,
+    /// Votes on the ballots which rank none of the continuing options
+    pub exhausted: f64,
+    /// Options elected in this round, the ones with more votes first
+    pub elected: Vec<OptionIdx>,
+    pub eliminated: Vec<OptionIdx>,
+    /// Transfers at the end of this round, going into the next one
+    pub transfers: Vec<StvTransfer>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct StvTally {
+    pub seats: u32,
+    /// Droop quota: votes which are enough to be elected
+    pub quota: f64,
+    pub rounds: Vec<StvRound>,
+    /// In the order of election. More winners than seats means
+    /// the options elected last are tied, none - no ballots at all
+    pub winners: Vec<OptionIdx>,
+}